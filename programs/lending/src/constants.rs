@@ -4,4 +4,26 @@ pub const METADATA: &str = "metadata";
 pub const LAMPORT_DECIMALS: u8 = 9;
 
 pub const LOAN: &str = "loan";
+pub const ASSET: &str = "asset";
+pub const PROFILE: &str = "profile";
+pub const LENDER_PAGE: &str = "lender_page";
+pub const BORROWER_PAGE: &str = "borrower_page";
 
+//  loan keys per LoanPage
+pub const LOAN_PAGE_SIZE: usize = 32;
+
+//  Config::pause_flags bits, each blocks one group of instructions on its own
+//  repayments, claims and withdrawals are never paused so borrowers cannot be trapped
+pub const PAUSE_CREATE: u8 = 1 << 0;
+pub const PAUSE_BORROW: u8 = 1 << 1;
+pub const PAUSE_FEES: u8 = 1 << 2;
+pub const PAUSE_ALL: u8 = PAUSE_CREATE | PAUSE_BORROW | PAUSE_FEES;
+
+pub const BPS_DENOMINATOR: u64 = 10_000;
+pub const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+pub const SECONDS_PER_YEAR: i64 = 365 * SECONDS_PER_DAY;
+
+//  oracle prices older than this many seconds are refused
+pub const MAX_PRICE_AGE: i64 = 60;
+//  oracle prices whose confidence exceeds this share of the price are refused
+pub const MAX_PRICE_CONF_BPS: u64 = 200;
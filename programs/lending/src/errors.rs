@@ -11,9 +11,21 @@ pub enum AgioError {
     #[msg("IncorrectAuthority")]
     IncorrectAuthority,
 
+    #[msg("Authority can only change through nominate and accept")]
+    AuthorityChangeNotAllowed,
+
+    #[msg("No pending authority")]
+    NoPendingAuthority,
+
     #[msg("Incorrect team wallet address")]
     IncorrectTeamWallet,
 
+    #[msg("Fee exceeds the configured maximum")]
+    FeeTooHigh,
+
+    #[msg("Expire duration must be at least one day")]
+    InvalidExpireDuration,
+
     #[msg("Loan already lended")]
     AlreadyLended,
 
@@ -29,9 +41,138 @@ pub enum AgioError {
     #[msg("Invalid collateral")]
     InvalidCollateral,
 
+    #[msg("Collateral is below the offered amount")]
+    InsufficientCollateral,
+
+    #[msg("Invalid loan mint")]
+    InvalidLoanMint,
+
+    #[msg("Mint has an unsupported token-2022 extension")]
+    UnsupportedMintExtension,
+
     #[msg("Overflow or underflow occured")]
     OverflowOrUnderflowOccurred,
 
     #[msg("Loan is activated")]
     LoanActivated,
+
+    #[msg("Loan already defaulted")]
+    AlreadyDefaulted,
+
+    #[msg("Loan is not borrowed")]
+    LoanNotBorrowed,
+
+    #[msg("Loan has not matured yet")]
+    LoanNotMatured,
+
+    #[msg("Loan already cancelled")]
+    AlreadyCancelled,
+
+    #[msg("Invalid loan status transition")]
+    InvalidStatusTransition,
+
+    #[msg("Invalid collateral ratio")]
+    InvalidCollateralRatio,
+
+    #[msg("Price account is not a verified pyth price update")]
+    InvalidPriceAccount,
+
+    #[msg("Price account is for a different feed")]
+    InvalidPriceFeed,
+
+    #[msg("Price is stale")]
+    StalePrice,
+
+    #[msg("Price must be positive")]
+    InvalidPrice,
+
+    #[msg("Price confidence interval is too wide")]
+    PriceConfidenceTooWide,
+
+    #[msg("Collateral value is below the required ratio")]
+    CollateralRatioTooLow,
+
+    #[msg("Invalid liquidation threshold")]
+    InvalidLiquidationThreshold,
+
+    #[msg("Invalid liquidation bonus")]
+    InvalidLiquidationBonus,
+
+    #[msg("Loan has no liquidation threshold")]
+    LiquidationDisabled,
+
+    #[msg("Loan is not undercollateralised")]
+    LoanHealthy,
+
+    #[msg("Loan already liquidated")]
+    AlreadyLiquidated,
+
+    #[msg("Repay amount must be greater than zero")]
+    InvalidRepayAmount,
+
+    #[msg("Invalid loan extension")]
+    InvalidExtension,
+
+    #[msg("Extension exceeds what the lender pre-approved")]
+    ExtensionNotApproved,
+
+    #[msg("Loan is past maturity")]
+    LoanPastMaturity,
+
+    #[msg("Offer changed since the borrower saw it")]
+    OfferChanged,
+
+    #[msg("Loan is a borrower request, not an offer")]
+    NotAnOffer,
+
+    #[msg("Loan is an offer, not a borrower request")]
+    NotARequest,
+
+    #[msg("Interest rate is above what the borrower requested")]
+    InterestRateTooHigh,
+
+    #[msg("Collateral nft is not a verified member of the offered collection")]
+    NotInCollection,
+
+    #[msg("Collateral is not a transferable non-fungible token")]
+    InvalidCollectionNft,
+
+    #[msg("Collection offers take exactly one nft and cannot be priced")]
+    InvalidCollectionTerms,
+
+    #[msg("Instruction is paused")]
+    Paused,
+
+    #[msg("Unknown pause flag")]
+    InvalidPauseFlags,
+
+    #[msg("Mint is not enabled for this side of a loan")]
+    AssetNotAllowed,
+
+    #[msg("Loan amount is outside the mint's allowed range")]
+    LoanAmountOutOfRange,
+
+    #[msg("Minimum loan amount is above the maximum")]
+    InvalidLoanAmountRange,
+
+    #[msg("Account is not an old-layout account of this type")]
+    InvalidAccountLayout,
+
+    #[msg("Account is already on the current layout")]
+    AlreadyMigrated,
+
+    #[msg("Portfolio profile and page are required for this loan")]
+    PortfolioRequired,
+
+    #[msg("Portfolio page does not list this loan")]
+    PortfolioMismatch,
+
+    #[msg("Lender cannot borrow their own loan")]
+    LenderIsBorrower,
+
+    #[msg("Borrow fee leaves nothing to disburse")]
+    NothingDisbursed,
+
+    #[msg("Loan duration must be at least one day")]
+    InvalidDuration,
 }
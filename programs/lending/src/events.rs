@@ -0,0 +1,267 @@
+use anchor_lang::prelude::*;
+
+use crate::state::LoanStatus;
+
+#[event]
+pub struct LoanCreated {
+    pub loan: Pubkey,
+    pub lender: Pubkey,
+    pub loan_mint: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub collateral_collection: Pubkey,
+    pub loan_amount: u64,
+    pub interest_rate: u64,
+    pub duration: i64,
+    pub collateral_amount: u64,
+    pub max_extension: i64,
+    pub min_collateral_ratio_bps: u64,
+    pub expire_date: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OfferUpdated {
+    pub loan: Pubkey,
+    pub lender: Pubkey,
+    pub loan_amount: u64,
+    pub interest_rate: u64,
+    pub duration: i64,
+    pub collateral_amount: u64,
+    pub offer_version: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LoanBorrowed {
+    pub loan: Pubkey,
+    pub lender: Pubkey,
+    pub borrower: Pubkey,
+    pub loan_mint: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub collateral_collection: Pubkey,
+    pub loan_amount: u64,
+    pub collateral_amount: u64,
+    pub fee_amount: u64,
+    pub borrow_fee_on_top: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LoanRepaid {
+    pub loan: Pubkey,
+    pub lender: Pubkey,
+    pub borrower: Pubkey,
+    pub loan_mint: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub principal: u64,
+    pub interest: u64,
+    pub fee_amount: u64,
+    pub collateral_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LoanPartiallyRepaid {
+    pub loan: Pubkey,
+    pub lender: Pubkey,
+    pub borrower: Pubkey,
+    pub loan_mint: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub principal: u64,
+    pub interest: u64,
+    pub fee_amount: u64,
+    pub collateral_released: u64,
+    pub principal_outstanding: u64,
+    pub fully_repaid: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LoanExtended {
+    pub loan: Pubkey,
+    pub lender: Pubkey,
+    pub borrower: Pubkey,
+    pub previous_duration: i64,
+    pub duration: i64,
+    pub previous_interest_rate: u64,
+    pub interest_rate: u64,
+    pub interest_paid: u64,
+    pub fee_amount: u64,
+    pub lender_signed: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LoanRequested {
+    pub request: Pubkey,
+    pub borrower: Pubkey,
+    pub loan_mint: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub loan_amount: u64,
+    pub max_interest_rate: u64,
+    pub duration: i64,
+    pub collateral_amount: u64,
+    pub expire_date: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RequestFilled {
+    pub request: Pubkey,
+    pub loan: Pubkey,
+    pub lender: Pubkey,
+    pub borrower: Pubkey,
+    pub loan_mint: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub loan_amount: u64,
+    pub interest_rate: u64,
+    pub collateral_amount: u64,
+    pub fee_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RequestCancelled {
+    pub request: Pubkey,
+    pub borrower: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub refund_amount: u64,
+    pub status: LoanStatus,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LoanCancelled {
+    pub loan: Pubkey,
+    pub lender: Pubkey,
+    pub loan_mint: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub refund_amount: u64,
+    pub status: LoanStatus,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CollateralClaimed {
+    pub loan: Pubkey,
+    pub lender: Pubkey,
+    pub borrower: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub collateral_amount: u64,
+    pub fee_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LoanLiquidated {
+    pub loan: Pubkey,
+    pub lender: Pubkey,
+    pub borrower: Pubkey,
+    pub liquidator: Pubkey,
+    pub loan_mint: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub principal: u64,
+    pub interest: u64,
+    pub lend_fee_amount: u64,
+    pub seized_amount: u64,
+    pub liquidation_fee_amount: u64,
+    pub returned_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigUpdated {
+    pub authority: Pubkey,
+    pub team_wallet: Pubkey,
+    pub lend_fee: u64,
+    pub borrow_fee: u64,
+    pub liquidation_fee: u64,
+    pub max_fee: u64,
+    pub expire_duration: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeesUpdated {
+    pub lend_fee: u64,
+    pub borrow_fee: u64,
+    pub liquidation_fee: u64,
+    pub borrow_fee_on_top: bool,
+    pub max_fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LiquidationParamsUpdated {
+    pub liquidation_threshold_bps: u64,
+    pub liquidation_bonus_bps: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TeamWalletUpdated {
+    pub previous_team_wallet: Pubkey,
+    pub team_wallet: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ExpireDurationUpdated {
+    pub expire_duration: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityNominated {
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityTransferred {
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GuardianUpdated {
+    pub previous_guardian: Pubkey,
+    pub guardian: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PauseUpdated {
+    pub previous_pause_flags: u8,
+    pub pause_flags: u8,
+    pub updated_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AssetConfigUpdated {
+    pub mint: Pubkey,
+    pub allowed_as_loan: bool,
+    pub allowed_as_collateral: bool,
+    pub min_loan_amount: u64,
+    pub max_loan_amount: u64,
+    pub borrow_fee: Option<u64>,
+    pub price_feed: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LoanMigrated {
+    pub legacy_loan: Pubkey,
+    pub loan: Pubkey,
+    pub version: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigMigrated {
+    pub version: u8,
+    pub timestamp: i64,
+}
@@ -0,0 +1,226 @@
+use crate::{constants::BPS_DENOMINATOR, errors::AgioError, state::{AssetConfig, Config}};
+use anchor_lang::prelude::*;
+
+//  `bps` basis points of `amount`, rounded up in favour of the protocol
+pub fn fee_bps(amount: u64, bps: u64) -> Result<u64> {
+    require!(bps <= BPS_DENOMINATOR, AgioError::FeeTooHigh);
+
+    let fee = (amount as u128)
+        .checked_mul(bps as u128)
+        .and_then(|v| v.checked_add(BPS_DENOMINATOR as u128 - 1))
+        .ok_or(AgioError::OverflowOrUnderflowOccurred)?
+        / BPS_DENOMINATOR as u128;
+
+    u64::try_from(fee).map_err(|_| error!(AgioError::OverflowOrUnderflowOccurred))
+}
+
+//  `bps` basis points of `amount`, rounded down in favour of the payer
+pub fn fee_bps_down(amount: u64, bps: u64) -> Result<u64> {
+    require!(bps <= BPS_DENOMINATOR, AgioError::FeeTooHigh);
+
+    let fee = (amount as u128)
+        .checked_mul(bps as u128)
+        .ok_or(AgioError::OverflowOrUnderflowOccurred)?
+        / BPS_DENOMINATOR as u128;
+
+    u64::try_from(fee).map_err(|_| error!(AgioError::OverflowOrUnderflowOccurred))
+}
+
+//  splits `amount` into (net, fee) so that net + fee == amount
+pub fn split(amount: u64, fee: u64) -> Result<(u64, u64)> {
+    let net = amount
+        .checked_sub(fee)
+        .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+    Ok((net, fee))
+}
+
+//  protocol cut of the interest a lender earns
+pub fn lend_fee(interest: u64, config: &Config) -> Result<u64> {
+    fee_bps(interest, config.lend_fee)
+}
+
+//  protocol cut of the principal a borrower receives, the loan mint's override wins
+//  up to max_fee, which may have been lowered since the override was set
+pub fn borrow_fee(principal: u64, config: &Config, loan_asset: &AssetConfig) -> Result<u64> {
+    let bps = loan_asset
+        .borrow_fee
+        .map_or(config.borrow_fee, |fee| fee.min(config.max_fee));
+    fee_bps(principal, bps)
+}
+
+//  what the borrower receives of `principal`, the fee is withheld from it unless paid on top
+//  a fee that takes the whole principal is refused rather than opening a loan for nothing
+pub fn disbursed(principal: u64, fee: u64, on_top: bool) -> Result<u64> {
+    let disbursed = if on_top { principal } else { split(principal, fee)?.0 };
+    require!(disbursed > 0, AgioError::NothingDisbursed);
+    Ok(disbursed)
+}
+
+//  protocol cut of collateral seized from a defaulted or liquidated loan, rounded down
+//  so seizing a single unit, such as a collection nft, never leaves the lender nothing
+pub fn liquidation_fee(collateral: u64, config: &Config) -> Result<u64> {
+    fee_bps_down(collateral, config.liquidation_fee)
+}
+
+//  splits seized collateral into (to the lender or liquidator, protocol cut)
+pub fn seized(collateral: u64, config: &Config) -> Result<(u64, u64)> {
+    split(collateral, liquidation_fee(collateral, config)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(lend_fee: u64, borrow_fee: u64, liquidation_fee: u64) -> Config {
+        Config {
+            version: Config::VERSION,
+            authority: Pubkey::new_unique(),
+            pending_authority: Pubkey::default(),
+            team_wallet: Pubkey::new_unique(),
+            lend_fee,
+            borrow_fee,
+            liquidation_fee,
+            borrow_fee_on_top: false,
+            max_fee: BPS_DENOMINATOR,
+            expire_duration: 7,
+            liquidation_threshold_bps: 0,
+            liquidation_bonus_bps: 0,
+            guardian: Pubkey::default(),
+            pause_flags: 0,
+            reserved: [0; 64],
+        }
+    }
+
+    fn asset(borrow_fee: Option<u64>) -> AssetConfig {
+        AssetConfig {
+            mint: Pubkey::new_unique(),
+            allowed_as_loan: true,
+            allowed_as_collateral: true,
+            min_loan_amount: 0,
+            max_loan_amount: 0,
+            borrow_fee,
+            price_feed: [0; 32],
+        }
+    }
+
+    #[test]
+    fn fee_bps_rounds_up() {
+        assert_eq!(fee_bps(10_000, 100).unwrap(), 100);
+        //  1% of 1 is a hundredth of a unit, charged as a whole one
+        assert_eq!(fee_bps(1, 100).unwrap(), 1);
+        assert_eq!(fee_bps(10_001, 100).unwrap(), 101);
+        //  nothing to round when either side is zero
+        assert_eq!(fee_bps(0, 100).unwrap(), 0);
+        assert_eq!(fee_bps(10_000, 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn fee_bps_is_bounded_by_the_denominator() {
+        assert_eq!(fee_bps(12_345, BPS_DENOMINATOR).unwrap(), 12_345);
+        assert_eq!(fee_bps(u64::MAX, BPS_DENOMINATOR).unwrap(), u64::MAX);
+        assert_eq!(fee_bps(12_345, BPS_DENOMINATOR + 1).unwrap_err(), AgioError::FeeTooHigh.into());
+    }
+
+    #[test]
+    fn split_balances() {
+        const MAX_FEE: u64 = 2_000;
+        //  999 is dust that no nonzero rate divides evenly
+        for amount in [0, 1, 999, u64::MAX] {
+            for bps in [0, 1, MAX_FEE, BPS_DENOMINATOR] {
+                let config = config(bps, bps, bps);
+                let fees = [
+                    ("borrow", borrow_fee(amount, &config, &asset(None)).unwrap()),
+                    ("lend", lend_fee(amount, &config).unwrap()),
+                    ("liquidation", liquidation_fee(amount, &config).unwrap()),
+                ];
+                for (name, fee) in fees {
+                    let (payout, taken) = split(amount, fee).unwrap();
+                    assert!(fee <= amount, "{name} fee on {amount} at {bps} bps");
+                    assert_eq!(taken, fee);
+                    assert_eq!(payout + fee, amount, "{name} fee on {amount} at {bps} bps");
+                }
+
+                //  what a borrow disburses is the same payout, refused when nothing is left
+                let (payout, fee) = split(amount, fees[0].1).unwrap();
+                match disbursed(amount, fee, false) {
+                    Ok(disbursed) => assert_eq!(disbursed, payout),
+                    Err(err) => {
+                        assert_eq!(payout, 0, "{amount} at {bps} bps");
+                        assert_eq!(err, AgioError::NothingDisbursed.into());
+                    }
+                }
+                assert_eq!(seized(amount, &config).unwrap(), split(amount, fees[2].1).unwrap());
+            }
+        }
+        assert_eq!(split(5, 6).unwrap_err(), AgioError::OverflowOrUnderflowOccurred.into());
+    }
+
+    #[test]
+    fn each_fee_reads_its_own_rate() {
+        let config = config(1_000, 100, 500);
+        assert_eq!(lend_fee(10_000, &config).unwrap(), 1_000);
+        assert_eq!(borrow_fee(10_000, &config, &asset(None)).unwrap(), 100);
+        assert_eq!(liquidation_fee(10_000, &config).unwrap(), 500);
+    }
+
+    #[test]
+    fn liquidation_fee_rounds_down() {
+        let config = config(0, 0, 500);
+        assert_eq!(liquidation_fee(10_019, &config).unwrap(), 500);
+        assert_eq!(liquidation_fee(19, &config).unwrap(), 0);
+        assert_eq!(liquidation_fee(1, &config).unwrap(), 0);
+        assert_eq!(fee_bps_down(12_345, BPS_DENOMINATOR + 1).unwrap_err(), AgioError::FeeTooHigh.into());
+    }
+
+    #[test]
+    fn claimed_collection_nft_goes_to_the_lender() {
+        //  a collection offer holds exactly one unit of collateral
+        for bps in [1, 500, BPS_DENOMINATOR - 1] {
+            assert_eq!(seized(1, &config(0, 0, bps)).unwrap(), (1, 0), "{bps} bps");
+        }
+        assert_eq!(seized(1_000, &config(0, 0, 500)).unwrap(), (950, 50));
+    }
+
+    #[test]
+    fn borrow_fee_prefers_the_asset_override() {
+        let config = config(0, 100, 0);
+        assert_eq!(borrow_fee(10_000, &config, &asset(Some(250))).unwrap(), 250);
+        assert_eq!(borrow_fee(10_000, &config, &asset(Some(0))).unwrap(), 0);
+    }
+
+    #[test]
+    fn borrow_fee_override_is_clamped_to_max_fee() {
+        let mut config = config(0, 100, 0);
+        config.max_fee = 200;
+        assert_eq!(borrow_fee(10_000, &config, &asset(Some(200))).unwrap(), 200);
+        assert_eq!(borrow_fee(10_000, &config, &asset(Some(5_000))).unwrap(), 200);
+    }
+
+    #[test]
+    fn zero_borrow_fee_disburses_the_whole_principal() {
+        let fee = borrow_fee(10_000, &config(0, 0, 0), &asset(None)).unwrap();
+        assert_eq!(fee, 0);
+        assert_eq!(disbursed(10_000, fee, false).unwrap(), 10_000);
+        assert_eq!(disbursed(10_000, fee, true).unwrap(), 10_000);
+    }
+
+    #[test]
+    fn max_borrow_fee_withheld_is_refused() {
+        let fee = borrow_fee(10_000, &config(0, BPS_DENOMINATOR, 0), &asset(None)).unwrap();
+        assert_eq!(fee, 10_000);
+        assert_eq!(disbursed(10_000, fee, false).unwrap_err(), AgioError::NothingDisbursed.into());
+        //  paid on top the borrower still receives the principal
+        assert_eq!(disbursed(10_000, fee, true).unwrap(), 10_000);
+        //  one basis point short of the cap leaves a single unit
+        let fee = borrow_fee(10_000, &config(0, BPS_DENOMINATOR - 1, 0), &asset(None)).unwrap();
+        assert_eq!(disbursed(10_000, fee, false).unwrap(), 1);
+    }
+
+    #[test]
+    fn rounded_up_fee_on_a_dust_principal_is_refused() {
+        let fee = borrow_fee(1, &config(0, 100, 0), &asset(None)).unwrap();
+        assert_eq!(fee, 1);
+        assert_eq!(disbursed(1, fee, false).unwrap_err(), AgioError::NothingDisbursed.into());
+        assert_eq!(disbursed(0, 0, true).unwrap_err(), AgioError::NothingDisbursed.into());
+    }
+}
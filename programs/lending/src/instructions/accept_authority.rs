@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::CONFIG, errors::AgioError, events::AuthorityTransferred, state::Config};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG.as_bytes()],
+        bump,
+    )]
+    global_config: Box<Account<'info, Config>>,
+
+    pub new_authority: Signer<'info>,
+}
+
+impl<'info> AcceptAuthority<'info> {
+    pub fn process(&mut self) -> Result<AuthorityTransferred> {
+        let global_config = &mut self.global_config;
+
+        require!(
+            global_config.pending_authority != Pubkey::default(),
+            AgioError::NoPendingAuthority
+        );
+        require!(
+            global_config.pending_authority == self.new_authority.key(),
+            AgioError::IncorrectAuthority
+        );
+
+        let previous_authority = global_config.authority;
+        global_config.authority = global_config.pending_authority;
+        global_config.pending_authority = Pubkey::default();
+
+        Ok(AuthorityTransferred {
+            previous_authority,
+            new_authority: global_config.authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
@@ -1,11 +1,19 @@
 use anchor_lang::prelude::*;
 
-use crate::{constants::LOAN, errors::AgioError, state::Loan, utils::{token_transfer_user, token_transfer_with_signer}};
-use anchor_spl::{associated_token::AssociatedToken, token::{Mint, Token, TokenAccount}};
+use crate::{constants::{ASSET, BORROWER_PAGE, CONFIG, LOAN, METADATA, PAUSE_BORROW, PROFILE}, errors::AgioError, events::LoanBorrowed, fees, oracle, portfolio, seeds::loan_signer_seeds, state::{AssetConfig, Config, Loan, LoanPage, LoanStatus, PortfolioRole, UserProfile}, utils::{is_native_mint, received_amount, sol_transfer_from_program_account, sol_transfer_from_user, token_close_with_signer, token_transfer_user, token_transfer_with_signer, validate_collection_nft, validate_mint, wrap_sol_from_user}};
+use anchor_spl::{associated_token::AssociatedToken, metadata::MetadataAccount, token_interface::{Mint, TokenInterface}};
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct BorrowLoan<'info> {
 
+    #[account(
+        seeds = [CONFIG.as_bytes()],
+        bump,
+        constraint = !global_config.is_paused(PAUSE_BORROW) @AgioError::Paused,
+    )]
+    global_config: Box<Account<'info, Config>>,
+
     #[account(
         mut,
         seeds = [LOAN.as_bytes(), &lender.key().to_bytes(), &loan_key.key().to_bytes()],
@@ -14,14 +22,31 @@ pub struct BorrowLoan<'info> {
     pub loan: Account<'info, Loan>,
 
 
-    #[account(mut)]
-    loan_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        constraint = loan_mint.key() == loan.loan_mint @AgioError::InvalidLoanMint,
+        mint::token_program = loan_token_program,
+    )]
+    loan_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [ASSET.as_bytes(), loan_mint.key().as_ref()],
+        bump,
+    )]
+    loan_asset: Box<Account<'info, AssetConfig>>,
+
+    //  keyed by the collection on a collection offer
+    #[account(
+        seeds = [ASSET.as_bytes(), loan.collateral_asset_mint().as_ref()],
+        bump,
+    )]
+    collateral_asset: Box<Account<'info, AssetConfig>>,
 
     #[account(
         mut,
         seeds = [
             loan.key().as_ref(),
-            anchor_spl::token::spl_token::ID.as_ref(),
+            collateral_token_program.key().as_ref(),
             collateral_mint.key().as_ref(),
         ],
         seeds::program = anchor_spl::associated_token::ID,
@@ -33,7 +58,7 @@ pub struct BorrowLoan<'info> {
         mut,
         seeds = [
             loan.key().as_ref(),
-            anchor_spl::token::spl_token::ID.as_ref(),
+            loan_token_program.key().as_ref(),
             loan_mint.key().as_ref(),
         ],
         seeds::program = anchor_spl::associated_token::ID,
@@ -45,7 +70,7 @@ pub struct BorrowLoan<'info> {
         mut,
         seeds = [
             borrower.key().as_ref(),
-            anchor_spl::token::spl_token::ID.as_ref(),
+            loan_token_program.key().as_ref(),
             loan_mint.key().as_ref(),
         ],
         bump,
@@ -57,7 +82,7 @@ pub struct BorrowLoan<'info> {
         mut,
         seeds = [
             borrower.key().as_ref(),
-            anchor_spl::token::spl_token::ID.as_ref(),
+            collateral_token_program.key().as_ref(),
             collateral_mint.key().as_ref(),
         ],
         bump,
@@ -65,19 +90,84 @@ pub struct BorrowLoan<'info> {
     )]
     borrower_collateral_ata: AccountInfo<'info>,
 
+    #[account(
+        mut,
+        seeds = [
+            team_wallet.key().as_ref(),
+            loan_token_program.key().as_ref(),
+            loan_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::associated_token::ID
+    )]
+    team_loan_ata: AccountInfo<'info>,
+
+    /// CHECK: should be same with the address in the global_config
+    #[account(
+        mut,
+        constraint = team_wallet.key() == global_config.team_wallet @AgioError::IncorrectTeamWallet
+    )]
+    pub team_wallet: AccountInfo<'info>,
+
     pub lender: AccountInfo<'info>,
 
-    pub collateral_mint: Account<'info, Mint>,
+    //  on a collection offer any nft of the collection, checked against collateral_metadata
+    #[account(
+        constraint = loan.accepts_collateral_mint(&collateral_mint.key()) @AgioError::InvalidCollateral,
+        mint::token_program = collateral_token_program,
+    )]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    //  metaplex metadata of collateral_mint, only read on a collection offer
+    #[account(
+        seeds = [METADATA.as_bytes(), anchor_spl::metadata::ID.as_ref(), collateral_mint.key().as_ref()],
+        seeds::program = anchor_spl::metadata::ID,
+        bump,
+    )]
+    pub collateral_metadata: Option<Box<Account<'info, MetadataAccount>>>,
 
-    pub loan_key: Account<'info, Mint>,
-    #[account(mut)]
+    pub loan_key: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        constraint = borrower.key() != loan.lender @AgioError::LenderIsBorrower,
+    )]
     pub borrower: Signer<'info>,
    
-    #[account(mut)]
-    pub program_collateral_ata: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+    //  pyth PriceUpdateV2 accounts, only read when the offer sets min_collateral_ratio_bps
+    pub loan_price_update: Option<AccountInfo<'info>>,
+    pub collateral_price_update: Option<AccountInfo<'info>>,
+
+    pub loan_token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
 
     pub associated_token_program: Program<'info, AssociatedToken>,
+    //  portfolio index, the page is the one the next listed loan lands in
+    #[account(
+        init_if_needed,
+        payer = borrower,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [PROFILE.as_bytes(), borrower.key().as_ref()],
+        bump,
+    )]
+    borrower_profile: Box<Account<'info, UserProfile>>,
+
+    #[account(
+        init_if_needed,
+        payer = borrower,
+        space = 8 + LoanPage::INIT_SPACE,
+        seeds = [BORROWER_PAGE.as_bytes(), borrower.key().as_ref(), &borrower_profile.as_borrower.next_page_index().to_le_bytes()],
+        bump,
+    )]
+    borrower_page: Box<Account<'info, LoanPage>>,
+
+    //  only counts the loan towards the lender's lifetime totals, required for offers listed when created
+    #[account(
+        mut,
+        seeds = [PROFILE.as_bytes(), lender.key().as_ref()],
+        bump,
+    )]
+    lender_profile: Option<Box<Account<'info, UserProfile>>>,
+
     pub system_program: Program<'info, System>,
 }
 
@@ -85,8 +175,9 @@ impl<'info> BorrowLoan<'info>{
     pub fn process(
         &mut self,
         collateral_amount: u64,
+        offer_version: u64,
         loan_bump: u8,
-    ) -> Result<()> {
+    ) -> Result<LoanBorrowed> {
 
         let clock = Clock::get()?;
         let current_time = clock.unix_timestamp;
@@ -96,13 +187,70 @@ impl<'info> BorrowLoan<'info>{
         let loan_mint_ata = &mut self.loan_mint_ata;
         let borrower_loan_ata = &mut self.borrower_loan_ata;
         let borrower_collateral_ata = &mut self.borrower_collateral_ata;
+        let team_loan_ata = &mut self.team_loan_ata;
         let borrower = &self.borrower;
+        let global_config = &self.global_config;
         
-        require!(loan.borrower == Pubkey::default(), AgioError::AlreadyLended);
+        require!(loan.status != LoanStatus::Requested, AgioError::NotAnOffer);
         require!(loan.expire_date > current_time, AgioError::AlreadyExpired);
+        require!(loan.offer_version == offer_version, AgioError::OfferChanged);
+        // the registry may have changed since the offer was made
+        self.loan_asset.require_loan(loan.loan_amount)?;
+        self.collateral_asset.require_collateral()?;
+        // the vault only holds what is left after the mint's transfer fee
+        let received_collateral = received_amount(&self.collateral_mint, collateral_amount)?;
+        loan.require_collateral(received_collateral)?;
+        loan.transition(LoanStatus::Active)?;
+
+        if loan.is_collection_offer() {
+            let collateral_metadata = self.collateral_metadata.as_ref().ok_or(AgioError::NotInCollection)?;
+            validate_collection_nft(&self.collateral_mint, collateral_metadata, &loan.collateral_collection)?;
+            validate_mint(&self.collateral_mint)?;
+
+            // the offer is now for this nft, every later instruction finds its vault by collateral_mint
+            loan.collateral_mint = self.collateral_mint.key();
+
+            // create loan collateral_mint ata, the offer could not open it without knowing the nft
+            anchor_spl::associated_token::create(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                anchor_spl::associated_token::Create {
+                    payer: self.borrower.to_account_info(),
+                    associated_token: loan_collateral_ata.to_account_info(),
+                    authority: loan.to_account_info(),
+
+                    mint: self.collateral_mint.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: self.collateral_token_program.to_account_info(),
+                }
+            ))?;
+        }
+
+        if loan.price_terms.is_priced() {
+            self.loan_asset.require_feed(&loan.price_terms.loan_price_feed)?;
+            self.collateral_asset.require_feed(&loan.price_terms.collateral_price_feed)?;
+            let loan_price_update = self.loan_price_update.as_ref().ok_or(AgioError::InvalidPriceAccount)?;
+            let collateral_price_update = self.collateral_price_update.as_ref().ok_or(AgioError::InvalidPriceAccount)?;
+            let loan_price = oracle::load_price(loan_price_update, &loan.price_terms.loan_price_feed, current_time)?;
+            let collateral_price = oracle::load_price(collateral_price_update, &loan.price_terms.collateral_price_feed, current_time)?;
+
+            require!(
+                oracle::meets_collateral_ratio(
+                    received_collateral,
+                    &collateral_price,
+                    self.collateral_mint.decimals,
+                    loan.loan_amount,
+                    &loan_price,
+                    self.loan_mint.decimals,
+                    loan.price_terms.min_collateral_ratio_bps,
+                )?,
+                AgioError::CollateralRatioTooLow
+            );
+        }
+
+        let native_loan = is_native_mint(&loan.loan_mint);
 
         //  create user wallet ata, if it doean't exit
-        if borrower_loan_ata.data_is_empty() {
+        if !native_loan && borrower_loan_ata.data_is_empty() {
             anchor_spl::associated_token::create(CpiContext::new(
                 self.associated_token_program.to_account_info(),
                 anchor_spl::associated_token::Create {
@@ -112,36 +260,150 @@ impl<'info> BorrowLoan<'info>{
 
                     mint: self.loan_mint.to_account_info(),
                     system_program: self.system_program.to_account_info(),
-                    token_program: self.token_program.to_account_info(),
+                    token_program: self.loan_token_program.to_account_info(),
                 }
             ))?;
         }
 
+        // create team_wallet loan_mint ata if it doesn't exit
+        if !native_loan && team_loan_ata.data_is_empty() {
+            anchor_spl::associated_token::create(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                anchor_spl::associated_token::Create {
+                    payer: self.borrower.to_account_info(),
+                    associated_token: team_loan_ata.to_account_info(),
+                    authority: self.team_wallet.to_account_info(),
+
+                    mint: self.loan_mint.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: self.loan_token_program.to_account_info(),
+                }
+            ))?;
+        }
         
         loan.borrower = self.borrower.key();
         loan.borrow_date = current_time;
+        loan.principal_outstanding = loan.loan_amount;
+        loan.interest_paid = 0;
+        loan.last_payment_ts = current_time;
+        loan.borrower_slot = portfolio::list(
+            PortfolioRole::Borrower,
+            loan.key(),
+            self.borrower.key(),
+            &mut self.borrower_profile,
+            &mut self.borrower_page,
+        )?;
+        portfolio::record(PortfolioRole::Borrower, loan.loan_amount, &mut self.borrower_profile)?;
+        match self.lender_profile.as_mut() {
+            Some(lender_profile) => portfolio::record(PortfolioRole::Lender, loan.loan_amount, lender_profile)?,
+            None => require!(loan.lender_slot == 0, AgioError::PortfolioRequired),
+        }
         
-        loan.collateral_amount = collateral_amount;
+        // borrower may post more than the offer asks, never less
+        loan.collateral_amount = received_collateral;
         
-        // loan_mint transfer from laon to borrower
-        token_transfer_with_signer(
-            loan_mint_ata.clone(), 
-            loan.to_account_info(), 
-            borrower_loan_ata.clone(), 
-            &self.token_program,
-            &[&[LOAN.as_bytes(), loan.key().as_ref(), &[loan_bump]]], 
-            loan.loan_amount
-        )?;
+        let lender_key = loan.lender;
+        let loan_key = self.loan_key.key();
+        let bump = [loan_bump];
+        let signer_seeds = loan_signer_seeds(&lender_key, &loan_key, &bump);
 
-        // collateral_mint transfer from borrower to loan
-        token_transfer_user(
-            borrower_collateral_ata.clone(), 
-            borrower, 
-            loan_collateral_ata.clone(), 
-            &self.token_program, 
-            collateral_amount
-        )?;
+        let fee_amount = fees::borrow_fee(loan.loan_amount, global_config, &self.loan_asset)?;
+        let disbursed_amount = fees::disbursed(loan.loan_amount, fee_amount, global_config.borrow_fee_on_top)?;
+
+        if native_loan {
+            let loan_info = loan.to_account_info();
+
+            // unwrap the loan_mint pda ata into the loan pda, its rent stays there for the lender
+            token_close_with_signer(
+                loan_mint_ata.clone(),
+                loan_info.clone(),
+                loan_info.clone(),
+                &self.loan_mint,
+                &self.loan_token_program,
+                &[&signer_seeds[..]],
+            )?;
+
+            // sol transfer from loan to borrower
+            sol_transfer_from_program_account(&loan_info, &borrower.to_account_info(), disbursed_amount)?;
+
+            // borrow fee transfer to team_wallet
+            if fee_amount > 0 {
+                if global_config.borrow_fee_on_top {
+                    sol_transfer_from_user(borrower, self.team_wallet.clone(), &self.system_program, fee_amount)?;
+                } else {
+                    sol_transfer_from_program_account(&loan_info, &self.team_wallet, fee_amount)?;
+                }
+            }
+        } else {
+            // loan_mint transfer from laon to borrower
+            token_transfer_with_signer(
+                loan_mint_ata.clone(), 
+                loan.to_account_info(), 
+                borrower_loan_ata.clone(), 
+                &self.loan_mint,
+                &self.loan_token_program,
+                &[&signer_seeds[..]], 
+                disbursed_amount
+            )?;
+
+            // borrow fee transfer to team_wallet
+            if fee_amount > 0 {
+                if global_config.borrow_fee_on_top {
+                    token_transfer_user(
+                        borrower_loan_ata.clone(), 
+                        borrower, 
+                        team_loan_ata.clone(), 
+                        &self.loan_mint,
+                        &self.loan_token_program, 
+                        fee_amount
+                    )?;
+                } else {
+                    token_transfer_with_signer(
+                        loan_mint_ata.clone(), 
+                        loan.to_account_info(), 
+                        team_loan_ata.clone(), 
+                        &self.loan_mint,
+                        &self.loan_token_program,
+                        &[&signer_seeds[..]], 
+                        fee_amount
+                    )?;
+                }
+            }
+        }
+
+        if is_native_mint(&loan.collateral_mint) {
+            // wrap borrower sol into the collateral_mint pda ata
+            wrap_sol_from_user(
+                borrower,
+                loan_collateral_ata.clone(),
+                &self.system_program,
+                &self.collateral_token_program,
+                collateral_amount
+            )?;
+        } else {
+            // collateral_mint transfer from borrower to loan
+            token_transfer_user(
+                borrower_collateral_ata.clone(), 
+                borrower, 
+                loan_collateral_ata.clone(), 
+                &self.collateral_mint,
+                &self.collateral_token_program, 
+                collateral_amount
+            )?;
+        }
 
-        Ok(())
+        Ok(LoanBorrowed {
+            loan: loan.key(),
+            lender: loan.lender,
+            borrower: loan.borrower,
+            loan_mint: loan.loan_mint,
+            collateral_mint: loan.collateral_mint,
+            collateral_collection: loan.collateral_collection,
+            loan_amount: loan.loan_amount,
+            collateral_amount: loan.collateral_amount,
+            fee_amount,
+            borrow_fee_on_top: global_config.borrow_fee_on_top,
+            timestamp: current_time,
+        })
     }
 }
\ No newline at end of file
@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::CONFIG, errors::AgioError, events::AuthorityNominated, state::Config};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelAuthorityNomination<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG.as_bytes()],
+        bump,
+        constraint = global_config.authority == authority.key() @AgioError::IncorrectAuthority
+    )]
+    global_config: Box<Account<'info, Config>>,
+
+    pub authority: Signer<'info>,
+}
+
+impl<'info> CancelAuthorityNomination<'info> {
+    pub fn process(&mut self) -> Result<AuthorityNominated> {
+        let global_config = &mut self.global_config;
+
+        require!(
+            global_config.pending_authority != Pubkey::default(),
+            AgioError::NoPendingAuthority
+        );
+        global_config.pending_authority = Pubkey::default();
+
+        Ok(AuthorityNominated {
+            authority: global_config.authority,
+            pending_authority: global_config.pending_authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
@@ -1,34 +1,52 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{associated_token::AssociatedToken, token::{Mint, Token}};
+use anchor_spl::{associated_token::AssociatedToken, token::accessor, token_interface::{Mint, TokenAccount, TokenInterface}};
 
-use crate::{constants::{CONFIG, LOAN}, errors::AgioError, state::{Config, Loan}, utils::token_transfer_with_signer};
+use crate::{constants::{LENDER_PAGE, LOAN, PROFILE}, errors::AgioError, events::LoanCancelled, portfolio, seeds::loan_signer_seeds, state::{Loan, LoanPage, LoanStatus, PortfolioRole, UserProfile}, utils::{is_native_mint, token_close_with_signer, token_transfer_with_signer}};
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct CancelLoan<'info> {
 
     #[account(
         mut,
-        seeds = [CONFIG.as_bytes()],
+        close = lender,
+        seeds = [LOAN.as_bytes(), &lender.key().to_bytes(), &loan_key.key().to_bytes()],
         bump,
     )]
-    global_config: Box<Account<'info, Config>>,
-    
+    loan: Account<'info, Loan>,
+
     #[account(
         mut,
-        
-        seeds = [LOAN.as_bytes(), &lender.key().to_bytes(), &loan_key.key().to_bytes()],
-        bump,
+        constraint = loan_mint.key() == loan.loan_mint @AgioError::InvalidLoanMint,
+        mint::token_program = loan_token_program,
     )]
-    loan: Account<'info, Loan>,
+    pub loan_mint: InterfaceAccount<'info, Mint>,
 
+    #[account(
+        mut,
+        constraint = collateral_mint.key() == loan.collateral_mint @AgioError::InvalidCollateral,
+        mint::token_program = collateral_token_program,
+    )]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
     
-    pub collateral_mint: Account<'info, Mint>,
-    
     #[account(
         mut,
         seeds = [
             loan.key().as_ref(),
-            anchor_spl::token::spl_token::ID.as_ref(),
+            loan_token_program.key().as_ref(),
+            loan_mint.key().as_ref(),
+            ],
+            seeds::program = anchor_spl::associated_token::ID,
+            bump,
+    )]
+    loan_mint_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    //  never opened for a collection offer, holds nothing unless someone sent tokens to it
+    #[account(
+        mut,
+        seeds = [
+            loan.key().as_ref(),
+            collateral_token_program.key().as_ref(),
             collateral_mint.key().as_ref(),
             ],
             seeds::program = anchor_spl::associated_token::ID,
@@ -39,20 +57,21 @@ pub struct CancelLoan<'info> {
     #[account(
         mut,
         seeds = [
-            team_wallet.key().as_ref(),
-            anchor_spl::token::spl_token::ID.as_ref(),
-            collateral_mint.key().as_ref(),
-        ],
+            lender.key().as_ref(),
+            loan_token_program.key().as_ref(),
+            loan_mint.key().as_ref(),
+            ],
         bump,
         seeds::program = anchor_spl::associated_token::ID
     )]
-    team_collateral_ata: AccountInfo<'info>,
-        
+    lender_loan_ata: AccountInfo<'info>,
+
+    //  receives whatever was sent to the collateral vault
     #[account(
         mut,
         seeds = [
             lender.key().as_ref(),
-            anchor_spl::token::spl_token::ID.as_ref(),
+            collateral_token_program.key().as_ref(),
             collateral_mint.key().as_ref(),
             ],
         bump,
@@ -60,17 +79,27 @@ pub struct CancelLoan<'info> {
     )]
     lender_collateral_ata: AccountInfo<'info>,
     
-    #[account(
-        mut,
-        constraint = team_wallet.key() == global_config.team_wallet @AgioError::IncorrectAuthority,
-    )]
-    pub team_wallet: AccountInfo<'info>,
-    
     #[account(mut)]
     pub lender: Signer<'info>,
-    pub token_program: Program<'info, Token>,
-    pub loan_key: Account<'info, Mint>,
+    pub loan_token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    pub loan_key: InterfaceAccount<'info, Mint>,
     pub associated_token_program: Program<'info, AssociatedToken>,
+    //  portfolio index, only required for loans listed when they were created
+    #[account(
+        mut,
+        seeds = [PROFILE.as_bytes(), lender.key().as_ref()],
+        bump,
+    )]
+    lender_profile: Option<Box<Account<'info, UserProfile>>>,
+
+    #[account(
+        mut,
+        seeds = [LENDER_PAGE.as_bytes(), lender.key().as_ref(), &loan.lender_page_index().to_le_bytes()],
+        bump,
+    )]
+    lender_page: Option<Box<Account<'info, LoanPage>>>,
+
     pub system_program: Program<'info, System>,
 
 }
@@ -79,84 +108,131 @@ impl<'info> CancelLoan<'info> {
     pub fn process(
         &mut self,
         loan_bump: u8
-    ) -> Result<()> {
+    ) -> Result<LoanCancelled> {
         let clock = Clock::get()?;
         let current_time = clock.unix_timestamp;
 
-        let global_config = &mut self.global_config;
         let loan = &mut self.loan;
-        let lender_collateral_ata = &mut self.lender_collateral_ata;
-        let loan_collateral_ata = &mut self.loan_collateral_ata;
-        let team_collateral_ata = &mut self.team_collateral_ata;
-
-        let expire_loan = loan.borrow_date
-            .checked_add(loan.duration)
-            .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+        let loan_mint_ata = &self.loan_mint_ata;
+        let loan_collateral_ata = &self.loan_collateral_ata;
+        let lender_loan_ata = &mut self.lender_loan_ata;
 
         require!(loan.lender == self.lender.key(), AgioError::IncorrectAuthority);
-        require!(
-            loan.borrower != Pubkey::default() || expire_loan < current_time, 
-            AgioError::LoanActivated
-        );
-        
-        // create lender collateral_mint ata if it doesn't exit
-        if lender_collateral_ata.data_is_empty() {
-            anchor_spl::associated_token::create(CpiContext::new(
-                self.associated_token_program.to_account_info(),
-                anchor_spl::associated_token::Create {
-                    payer: self.lender.to_account_info(),
-                    associated_token: lender_collateral_ata.to_account_info(),
-                    authority: self.lender.to_account_info(),
+        require!(loan.status != LoanStatus::Requested, AgioError::NotAnOffer);
 
-                    mint: self.collateral_mint.to_account_info(),
-                    system_program: self.system_program.to_account_info(),
-                    token_program: self.token_program.to_account_info(),
-                }
-            ))?;
+        // an offer nobody took is cancelled before expire_date, expired after it
+        if current_time < loan.expire_date {
+            loan.transition(LoanStatus::Cancelled)?;
+        } else {
+            loan.transition(LoanStatus::Expired)?;
         }
+        
+        portfolio::unlist(
+            PortfolioRole::Lender,
+            loan.key(),
+            loan.lender_slot,
+            self.lender_profile.as_mut(),
+            self.lender_page.as_mut(),
+            &self.lender.to_account_info(),
+        )?;
 
-        // create team_wallet collateral_mint ata if it doesn't exit
-        if team_collateral_ata.data_is_empty() {
+        let native_loan = is_native_mint(&loan.loan_mint);
+
+        // create lender loan_mint ata if it doesn't exit
+        if !native_loan && lender_loan_ata.data_is_empty() {
             anchor_spl::associated_token::create(CpiContext::new(
                 self.associated_token_program.to_account_info(),
                 anchor_spl::associated_token::Create {
                     payer: self.lender.to_account_info(),
-                    associated_token: team_collateral_ata.to_account_info(),
+                    associated_token: lender_loan_ata.to_account_info(),
                     authority: self.lender.to_account_info(),
 
-                    mint: self.collateral_mint.to_account_info(),
+                    mint: self.loan_mint.to_account_info(),
                     system_program: self.system_program.to_account_info(),
-                    token_program: self.token_program.to_account_info(),
+                    token_program: self.loan_token_program.to_account_info(),
                 }
             ))?;
         }
 
+        let lender_key = loan.lender;
+        let loan_key = self.loan_key.key();
+        let bump = [loan_bump];
+        let signer_seeds = loan_signer_seeds(&lender_key, &loan_key, &bump);
+
+        let refund_amount = loan_mint_ata.amount;
+
+        // refund the offered principal to lender, wsol is unwrapped by the close below
+        if !native_loan {
+            token_transfer_with_signer(
+                loan_mint_ata.to_account_info(),
+                loan.to_account_info(), 
+                lender_loan_ata.clone(), 
+                &self.loan_mint,
+                &self.loan_token_program, 
+                &[&signer_seeds[..]], 
+                refund_amount
+            )?;
+        }
 
-        // trnasfer collateral_mint to lender and teamWallet
-        let fee_amount = (loan.collateral_amount / 100)
-            .checked_mul(global_config.lend_fee)
-            .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
-
-
-        token_transfer_with_signer(
-            loan_collateral_ata.clone(),
-            loan.to_account_info(), 
-            lender_collateral_ata.clone(), 
-            &self.token_program, 
-            &[&[LOAN.as_bytes(), loan.key().as_ref(), &[loan_bump]]], 
-            loan.collateral_amount - fee_amount
+        // close loan vaults and refund rent to lender
+        token_close_with_signer(
+            loan_mint_ata.to_account_info(),
+            self.lender.to_account_info(),
+            loan.to_account_info(),
+            &self.loan_mint,
+            &self.loan_token_program,
+            &[&signer_seeds[..]],
         )?;
 
-        token_transfer_with_signer(
-            loan_collateral_ata.clone(),
-            self.team_wallet.to_account_info(), 
-            self.team_collateral_ata.clone(), 
-            &self.token_program, 
-            &[&[LOAN.as_bytes(), loan.key().as_ref(), &[loan_bump]]], 
-            fee_amount
-        )?;
+        // sweep anything sent to the collateral vault to the lender so it always closes,
+        // its authority is gone once the loan is closed, wsol is unwrapped by the close
+        if !loan_collateral_ata.data_is_empty() {
+            let swept_amount = accessor::amount(loan_collateral_ata)?;
+            if swept_amount > 0 && !is_native_mint(&loan.collateral_mint) {
+                if self.lender_collateral_ata.data_is_empty() {
+                    anchor_spl::associated_token::create(CpiContext::new(
+                        self.associated_token_program.to_account_info(),
+                        anchor_spl::associated_token::Create {
+                            payer: self.lender.to_account_info(),
+                            associated_token: self.lender_collateral_ata.to_account_info(),
+                            authority: self.lender.to_account_info(),
+
+                            mint: self.collateral_mint.to_account_info(),
+                            system_program: self.system_program.to_account_info(),
+                            token_program: self.collateral_token_program.to_account_info(),
+                        }
+                    ))?;
+                }
 
+                token_transfer_with_signer(
+                    loan_collateral_ata.clone(),
+                    loan.to_account_info(),
+                    self.lender_collateral_ata.clone(),
+                    &self.collateral_mint,
+                    &self.collateral_token_program,
+                    &[&signer_seeds[..]],
+                    swept_amount
+                )?;
+            }
+
+            token_close_with_signer(
+                loan_collateral_ata.clone(),
+                self.lender.to_account_info(),
+                loan.to_account_info(),
+                &self.collateral_mint,
+                &self.collateral_token_program,
+                &[&signer_seeds[..]],
+            )?;
+        }
         
-        Ok(())
+        Ok(LoanCancelled {
+            loan: loan.key(),
+            lender: loan.lender,
+            loan_mint: loan.loan_mint,
+            collateral_mint: loan.collateral_mint,
+            refund_amount,
+            status: loan.status,
+            timestamp: current_time,
+        })
     }
 }
\ No newline at end of file
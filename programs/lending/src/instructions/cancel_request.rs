@@ -0,0 +1,162 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface}};
+
+use crate::{constants::{BORROWER_PAGE, LOAN, PROFILE}, errors::AgioError, events::RequestCancelled, portfolio, seeds::loan_signer_seeds, state::{Loan, LoanPage, LoanStatus, PortfolioRole, UserProfile}, utils::{is_native_mint, token_close_with_signer, token_transfer_with_signer}};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelRequest<'info> {
+
+    #[account(
+        mut,
+        close = borrower,
+        seeds = [LOAN.as_bytes(), &borrower.key().to_bytes(), &loan_key.key().to_bytes()],
+        bump,
+    )]
+    request: Account<'info, Loan>,
+
+    #[account(
+        mut,
+        constraint = collateral_mint.key() == request.collateral_mint @AgioError::InvalidCollateral,
+        mint::token_program = collateral_token_program,
+    )]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [
+            request.key().as_ref(),
+            collateral_token_program.key().as_ref(),
+            collateral_mint.key().as_ref(),
+        ],
+        seeds::program = anchor_spl::associated_token::ID,
+        bump,
+    )]
+    request_collateral_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [
+            borrower.key().as_ref(),
+            collateral_token_program.key().as_ref(),
+            collateral_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::associated_token::ID
+    )]
+    borrower_collateral_ata: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+
+    pub loan_key: InterfaceAccount<'info, Mint>,
+
+    //  portfolio index, only required for requests listed when they were created
+    #[account(
+        mut,
+        seeds = [PROFILE.as_bytes(), borrower.key().as_ref()],
+        bump,
+    )]
+    borrower_profile: Option<Box<Account<'info, UserProfile>>>,
+
+    #[account(
+        mut,
+        seeds = [BORROWER_PAGE.as_bytes(), borrower.key().as_ref(), &request.borrower_page_index().to_le_bytes()],
+        bump,
+    )]
+    borrower_page: Option<Box<Account<'info, LoanPage>>>,
+
+    pub system_program: Program<'info, System>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> CancelRequest<'info> {
+    pub fn process(
+        &mut self,
+        request_bump: u8
+    ) -> Result<RequestCancelled> {
+        let clock = Clock::get()?;
+        let current_time = clock.unix_timestamp;
+
+        let request = &mut self.request;
+        let request_collateral_ata = &self.request_collateral_ata;
+        let borrower_collateral_ata = &self.borrower_collateral_ata;
+
+        require!(request.borrower == self.borrower.key(), AgioError::IncorrectAuthority);
+        require!(request.status == LoanStatus::Requested, AgioError::NotARequest);
+
+        // a request nobody filled is cancelled before expire_date, expired after it
+        if current_time < request.expire_date {
+            request.transition(LoanStatus::Cancelled)?;
+        } else {
+            request.transition(LoanStatus::Expired)?;
+        }
+
+        portfolio::unlist(
+            PortfolioRole::Borrower,
+            request.key(),
+            request.borrower_slot,
+            self.borrower_profile.as_mut(),
+            self.borrower_page.as_mut(),
+            &self.borrower.to_account_info(),
+        )?;
+
+        let native_collateral = is_native_mint(&request.collateral_mint);
+
+        // create borrower collateral_mint ata if it doesn't exit
+        if !native_collateral && borrower_collateral_ata.data_is_empty() {
+            anchor_spl::associated_token::create(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                anchor_spl::associated_token::Create {
+                    payer: self.borrower.to_account_info(),
+                    associated_token: borrower_collateral_ata.to_account_info(),
+                    authority: self.borrower.to_account_info(),
+
+                    mint: self.collateral_mint.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: self.collateral_token_program.to_account_info(),
+                }
+            ))?;
+        }
+
+        let borrower_key = request.borrower;
+        let loan_key = self.loan_key.key();
+        let bump = [request_bump];
+        let signer_seeds = loan_signer_seeds(&borrower_key, &loan_key, &bump);
+
+        let refund_amount = request_collateral_ata.amount;
+
+        // refund the collateral to borrower, wsol is unwrapped by the close below
+        if !native_collateral && refund_amount > 0 {
+            token_transfer_with_signer(
+                request_collateral_ata.to_account_info(),
+                request.to_account_info(),
+                borrower_collateral_ata.clone(),
+                &self.collateral_mint,
+                &self.collateral_token_program,
+                &[&signer_seeds[..]],
+                refund_amount
+            )?;
+        }
+
+        // close collateral vault and refund rent to borrower
+        token_close_with_signer(
+            request_collateral_ata.to_account_info(),
+            self.borrower.to_account_info(),
+            request.to_account_info(),
+            &self.collateral_mint,
+            &self.collateral_token_program,
+            &[&signer_seeds[..]],
+        )?;
+
+        Ok(RequestCancelled {
+            request: request.key(),
+            borrower: request.borrower,
+            collateral_mint: request.collateral_mint,
+            refund_amount,
+            status: request.status,
+            timestamp: current_time,
+        })
+    }
+}
@@ -0,0 +1,301 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface}};
+
+use crate::{constants::{BORROWER_PAGE, CONFIG, LENDER_PAGE, LOAN, PROFILE}, errors::AgioError, events::CollateralClaimed, fees, portfolio, seeds::loan_signer_seeds, state::{Config, Loan, LoanPage, LoanStatus, PortfolioRole, UserProfile}, utils::{is_native_mint, sol_transfer_from_program_account, token_close_if_empty_with_signer, token_close_with_signer, token_transfer_with_signer}};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimCollateral<'info> {
+
+    #[account(
+        seeds = [CONFIG.as_bytes()],
+        bump,
+    )]
+    global_config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        close = lender,
+        seeds = [LOAN.as_bytes(), &lender.key().to_bytes(), &loan_key.key().to_bytes()],
+        bump,
+    )]
+    loan: Account<'info, Loan>,
+
+    #[account(
+        mut,
+        constraint = collateral_mint.key() == loan.collateral_mint @AgioError::InvalidCollateral,
+        mint::token_program = collateral_token_program,
+    )]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = loan_mint.key() == loan.loan_mint @AgioError::InvalidLoanMint,
+        mint::token_program = loan_token_program,
+    )]
+    pub loan_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [
+            loan.key().as_ref(),
+            collateral_token_program.key().as_ref(),
+            collateral_mint.key().as_ref(),
+        ],
+        seeds::program = anchor_spl::associated_token::ID,
+        bump,
+    )]
+    loan_collateral_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [
+            loan.key().as_ref(),
+            loan_token_program.key().as_ref(),
+            loan_mint.key().as_ref(),
+        ],
+        seeds::program = anchor_spl::associated_token::ID,
+        bump,
+    )]
+    loan_mint_ata: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            lender.key().as_ref(),
+            collateral_token_program.key().as_ref(),
+            collateral_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::associated_token::ID
+    )]
+    lender_collateral_ata: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            team_wallet.key().as_ref(),
+            collateral_token_program.key().as_ref(),
+            collateral_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::associated_token::ID
+    )]
+    team_collateral_ata: AccountInfo<'info>,
+
+    /// CHECK: should be same with the address in the global_config
+    #[account(
+        mut,
+        constraint = team_wallet.key() == global_config.team_wallet @AgioError::IncorrectTeamWallet,
+    )]
+    pub team_wallet: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub lender: Signer<'info>,
+
+    //  receives the rent of an emptied borrower portfolio page, and of the collateral vault
+    //  on a collection offer, which the borrower opened
+    #[account(
+        mut,
+        constraint = borrower.key() == loan.borrower @AgioError::IncorrectAuthority,
+    )]
+    pub borrower: AccountInfo<'info>,
+
+    pub loan_key: InterfaceAccount<'info, Mint>,
+
+    //  portfolio index, only required for loans listed when they were created
+    #[account(
+        mut,
+        seeds = [PROFILE.as_bytes(), lender.key().as_ref()],
+        bump,
+    )]
+    lender_profile: Option<Box<Account<'info, UserProfile>>>,
+
+    #[account(
+        mut,
+        seeds = [LENDER_PAGE.as_bytes(), lender.key().as_ref(), &loan.lender_page_index().to_le_bytes()],
+        bump,
+    )]
+    lender_page: Option<Box<Account<'info, LoanPage>>>,
+
+    #[account(
+        mut,
+        seeds = [PROFILE.as_bytes(), borrower.key().as_ref()],
+        bump,
+    )]
+    borrower_profile: Option<Box<Account<'info, UserProfile>>>,
+
+    #[account(
+        mut,
+        seeds = [BORROWER_PAGE.as_bytes(), borrower.key().as_ref(), &loan.borrower_page_index().to_le_bytes()],
+        bump,
+    )]
+    borrower_page: Option<Box<Account<'info, LoanPage>>>,
+
+    pub system_program: Program<'info, System>,
+    pub loan_token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> ClaimCollateral<'info> {
+    pub fn process(
+        &mut self,
+        loan_bump: u8
+    ) -> Result<CollateralClaimed> {
+        let clock = Clock::get()?;
+        let current_time = clock.unix_timestamp;
+
+        let global_config = &self.global_config;
+        let loan = &mut self.loan;
+        let loan_collateral_ata = &self.loan_collateral_ata;
+        let loan_mint_ata = &self.loan_mint_ata;
+        let lender_collateral_ata = &mut self.lender_collateral_ata;
+        let team_collateral_ata = &mut self.team_collateral_ata;
+
+        require!(loan.lender == self.lender.key(), AgioError::IncorrectAuthority);
+        loan.transition(LoanStatus::Defaulted)?;
+
+        require!(current_time > loan.maturity()?, AgioError::LoanNotMatured);
+
+        portfolio::unlist(
+            PortfolioRole::Lender,
+            loan.key(),
+            loan.lender_slot,
+            self.lender_profile.as_mut(),
+            self.lender_page.as_mut(),
+            &self.lender.to_account_info(),
+        )?;
+        portfolio::unlist(
+            PortfolioRole::Borrower,
+            loan.key(),
+            loan.borrower_slot,
+            self.borrower_profile.as_mut(),
+            self.borrower_page.as_mut(),
+            &self.borrower.to_account_info(),
+        )?;
+        if let Some(borrower_profile) = self.borrower_profile.as_mut() {
+            borrower_profile.default_count = borrower_profile.default_count
+                .checked_add(1)
+                .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+        }
+
+        let native_collateral = is_native_mint(&loan.collateral_mint);
+
+        // create lender collateral_mint ata if it doesn't exit
+        if !native_collateral && lender_collateral_ata.data_is_empty() {
+            anchor_spl::associated_token::create(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                anchor_spl::associated_token::Create {
+                    payer: self.lender.to_account_info(),
+                    associated_token: lender_collateral_ata.to_account_info(),
+                    authority: self.lender.to_account_info(),
+
+                    mint: self.collateral_mint.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: self.collateral_token_program.to_account_info(),
+                }
+            ))?;
+        }
+
+        // create team_wallet collateral_mint ata if it doesn't exit
+        if !native_collateral && team_collateral_ata.data_is_empty() {
+            anchor_spl::associated_token::create(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                anchor_spl::associated_token::Create {
+                    payer: self.lender.to_account_info(),
+                    associated_token: team_collateral_ata.to_account_info(),
+                    authority: self.team_wallet.to_account_info(),
+
+                    mint: self.collateral_mint.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: self.collateral_token_program.to_account_info(),
+                }
+            ))?;
+        }
+
+        // seize whatever the vault holds, minus the protocol fee
+        let collateral_amount = loan_collateral_ata.amount;
+        let (lender_amount, fee_amount) = fees::seized(collateral_amount, global_config)?;
+
+        let lender_key = loan.lender;
+        let loan_key = self.loan_key.key();
+        let bump = [loan_bump];
+        let signer_seeds = loan_signer_seeds(&lender_key, &loan_key, &bump);
+
+        if native_collateral {
+            let loan_info = loan.to_account_info();
+
+            // unwrap the collateral_mint pda ata into the loan pda, its rent stays there for the lender
+            token_close_with_signer(
+                loan_collateral_ata.to_account_info(),
+                loan_info.clone(),
+                loan_info.clone(),
+                &self.collateral_mint,
+                &self.collateral_token_program,
+                &[&signer_seeds[..]],
+            )?;
+
+            // sol transfer from loan to lender and team_wallet
+            sol_transfer_from_program_account(&loan_info, &self.lender.to_account_info(), lender_amount)?;
+            if fee_amount > 0 {
+                sol_transfer_from_program_account(&loan_info, &self.team_wallet, fee_amount)?;
+            }
+        } else {
+            // transfer collateral_mint pda to lender
+            token_transfer_with_signer(
+                loan_collateral_ata.to_account_info(),
+                loan.to_account_info(),
+                lender_collateral_ata.clone(),
+                &self.collateral_mint,
+                &self.collateral_token_program,
+                &[&signer_seeds[..]],
+                lender_amount
+            )?;
+
+            // transfer collateral_mint fee pda to team_wallet
+            if fee_amount > 0 {
+                token_transfer_with_signer(
+                    loan_collateral_ata.to_account_info(),
+                    loan.to_account_info(),
+                    team_collateral_ata.clone(),
+                    &self.collateral_mint,
+                    &self.collateral_token_program,
+                    &[&signer_seeds[..]],
+                    fee_amount
+                )?;
+            }
+
+            // close collateral vault and refund rent to whoever opened it, the borrower on a collection offer
+            let collateral_rent_to = if loan.is_collection_offer() { self.borrower.to_account_info() } else { self.lender.to_account_info() };
+            token_close_with_signer(
+                loan_collateral_ata.to_account_info(),
+                collateral_rent_to,
+                loan.to_account_info(),
+                &self.collateral_mint,
+                &self.collateral_token_program,
+                &[&signer_seeds[..]],
+            )?;
+        }
+
+        // close loan_mint vault if still open and refund rent to lender
+        token_close_if_empty_with_signer(
+            loan_mint_ata.clone(),
+            self.lender.to_account_info(),
+            loan.to_account_info(),
+            &self.loan_mint,
+            &self.loan_token_program,
+            &[&signer_seeds[..]],
+        )?;
+
+        Ok(CollateralClaimed {
+            loan: loan.key(),
+            lender: loan.lender,
+            borrower: loan.borrower,
+            collateral_mint: loan.collateral_mint,
+            collateral_amount,
+            fee_amount,
+            timestamp: current_time,
+        })
+    }
+}
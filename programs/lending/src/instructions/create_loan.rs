@@ -1,26 +1,42 @@
 use anchor_lang::prelude::*;
-use chrono::Duration;
 
 use crate::{
-    constants::{CONFIG, LOAN}, 
+    constants::{ASSET, CONFIG, LENDER_PAGE, LOAN, PAUSE_CREATE, PROFILE}, 
     errors::AgioError, 
-    state::{Config, Loan}, 
-    utils::{ token_transfer_user}
+    events::LoanCreated,
+    interest,
+    portfolio,
+    state::{AssetConfig, Config, Loan, LoanPage, LoanStatus, PortfolioRole, PriceTerms, UserProfile}, 
+    utils::{is_native_mint, received_amount, token_transfer_user, validate_mint, wrap_sol_from_user}
 };
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{Token, Mint},
+    token_interface::{Mint, TokenInterface},
 };
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct CreateLoan<'info> {
     #[account(
         mut,
         seeds = [CONFIG.as_bytes()],
         bump,
+        constraint = !global_config.is_paused(PAUSE_CREATE) @AgioError::Paused,
     )]
     global_config: Box<Account<'info, Config>>,
 
+    #[account(
+        seeds = [ASSET.as_bytes(), loan_mint.key().as_ref()],
+        bump,
+    )]
+    loan_asset: Box<Account<'info, AssetConfig>>,
+
+    #[account(
+        seeds = [ASSET.as_bytes(), collateral_mint.key().as_ref()],
+        bump,
+    )]
+    collateral_asset: Box<Account<'info, AssetConfig>>,
+
     //  team wallet
     /// CHECK: should be same with the address in the global_config
     #[account(
@@ -32,23 +48,27 @@ pub struct CreateLoan<'info> {
     #[account(
         init, 
         payer = lender, 
-        space = 8 + std::mem::size_of::<Loan>(),
-        seeds = [LOAN.as_bytes(), &loan_key.key().to_bytes()],
+        space = 8 + Loan::INIT_SPACE,
+        seeds = [LOAN.as_bytes(), &lender.key().to_bytes(), &loan_key.key().to_bytes()],
         bump,
     )]
     loan: Account<'info, Loan>,
 
-    #[account(mut)]
-    collateral_mint: Account<'info, Mint>,
+    #[account(
+        mint::token_program = collateral_token_program,
+    )]
+    collateral_mint: InterfaceAccount<'info, Mint>,
 
-    #[account(mut)]
-    loan_mint: Account<'info, Mint>,
+    #[account(
+        mint::token_program = loan_token_program,
+    )]
+    loan_mint: InterfaceAccount<'info, Mint>,
 
     #[account(
         mut,
         seeds = [
             loan.key().as_ref(),
-            anchor_spl::token::spl_token::ID.as_ref(),
+            collateral_token_program.key().as_ref(),
             collateral_mint.key().as_ref(),
         ],
         seeds::program = anchor_spl::associated_token::ID,
@@ -60,7 +80,7 @@ pub struct CreateLoan<'info> {
         mut,
         seeds = [
             loan.key().as_ref(),
-            anchor_spl::token::spl_token::ID.as_ref(),
+            loan_token_program.key().as_ref(),
             loan_mint.key().as_ref(),
         ],
         seeds::program = anchor_spl::associated_token::ID,
@@ -72,7 +92,7 @@ pub struct CreateLoan<'info> {
         mut,
         seeds = [
             lender.key().as_ref(),
-            anchor_spl::token::spl_token::ID.as_ref(),
+            loan_token_program.key().as_ref(),
             loan_mint.key().as_ref(),
         ],
         bump,
@@ -83,57 +103,102 @@ pub struct CreateLoan<'info> {
     #[account(mut)]
     pub lender: Signer<'info>,
 
-    pub loan_key: Account<'info, Mint>,
+    pub loan_key: InterfaceAccount<'info, Mint>,
+
+    //  portfolio index, the page is the one the next listed loan lands in
+    #[account(
+        init_if_needed,
+        payer = lender,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [PROFILE.as_bytes(), lender.key().as_ref()],
+        bump,
+    )]
+    lender_profile: Box<Account<'info, UserProfile>>,
+
+    #[account(
+        init_if_needed,
+        payer = lender,
+        space = 8 + LoanPage::INIT_SPACE,
+        seeds = [LENDER_PAGE.as_bytes(), lender.key().as_ref(), &lender_profile.as_lender.next_page_index().to_le_bytes()],
+        bump,
+    )]
+    lender_page: Box<Account<'info, LoanPage>>,
 
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    pub loan_token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub rent: Sysvar<'info, Rent>,
 
 }
 
 impl<'info> CreateLoan<'info>{
+    #[allow(clippy::too_many_arguments)]
     pub fn process(
         &mut self,
         loan_amount: u64,
         interest_rate: u64,
         duration: u64,
         collateral_amount: u64,
-    ) -> Result<()> {
+        max_extension: u64,
+        price_terms: PriceTerms,
+        collateral_is_collection: bool,
+    ) -> Result<LoanCreated> {
 
         let global_config = &mut self.global_config;
         let loan_mint_ata = &mut self.loan_mint_ata;
         let loan_collateral_ata = &mut self.loan_collateral_ata;
-        let collateral_mint = &mut self.collateral_mint;
-        let loan_mint = &mut self.loan_mint;
+        let collateral_mint = &self.collateral_mint;
+        let loan_mint = &self.loan_mint;
         let lender_loan_ata = &mut self.lender_loan_ata;
-        
-        let one_day = Duration::days(1).num_seconds();
 
+        validate_mint(loan_mint)?;
+        validate_mint(collateral_mint)?;
+        self.loan_asset.require_loan(loan_amount)?;
+        self.collateral_asset.require_collateral()?;
+        
         // create loan pda
         let loan = &mut self.loan;
         
+        loan.version = Loan::VERSION;
         loan.lender = self.lender.key();
         loan.borrower = Pubkey::default(); // No lender yet
         
         loan.loan_mint = loan_mint.key();
-        loan.loan_amount = loan_amount;
+        // the vault only holds what is left after the mint's transfer fee
+        loan.loan_amount = received_amount(loan_mint, loan_amount)?;
         loan.interest_rate = interest_rate;
-        loan.duration = one_day
-        .checked_mul(duration as i64)
-        .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+        loan.duration = interest::term_seconds(duration)?;
         loan.collateral_mint = collateral_mint.key();
         loan.collateral_amount = collateral_amount;
+        if collateral_is_collection {
+            // collateral_mint names the collection until borrow_loan swaps in the posted nft
+            require!(collateral_amount == 1 && !price_terms.has_feeds(), AgioError::InvalidCollectionTerms);
+            loan.collateral_collection = collateral_mint.key();
+        }
+        loan.max_extension = interest::days_to_seconds(max_extension)?;
+
+        price_terms.validate()?;
+        if price_terms.has_feeds() {
+            self.loan_asset.require_feed(&price_terms.loan_price_feed)?;
+            self.collateral_asset.require_feed(&price_terms.collateral_price_feed)?;
+        }
+        loan.price_terms = price_terms;
     
-        let expire_duration = one_day
-            .checked_mul(global_config.expire_duration as i64)
-            .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+        let expire_duration = interest::days_to_seconds(global_config.expire_duration.into())?;
 
         loan.create_date = Clock::get()?.unix_timestamp;
         loan.expire_date = loan.create_date
             .checked_add(expire_duration)
             .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
-        loan.repaid = false;
+        loan.status = LoanStatus::Offered;
+        loan.lender_slot = portfolio::list(
+            PortfolioRole::Lender,
+            loan.key(),
+            self.lender.key(),
+            &mut self.lender_profile,
+            &mut self.lender_page,
+        )?;
 
         // create loan_mint ata
         anchor_spl::associated_token::create(CpiContext::new(
@@ -145,32 +210,62 @@ impl<'info> CreateLoan<'info>{
 
                 mint: loan_mint.to_account_info(),
                 system_program: self.system_program.to_account_info(),
-                token_program: self.token_program.to_account_info(),
+                token_program: self.loan_token_program.to_account_info(),
             }
         ))?;
 
-        // create collateral_loan_ata
-        anchor_spl::associated_token::create(CpiContext::new(
-            self.associated_token_program.to_account_info(),
-            anchor_spl::associated_token::Create {
-                payer: self.lender.to_account_info(),
-                associated_token: loan_collateral_ata.to_account_info(),
-                authority: self.loan.to_account_info(),
+        // create collateral_loan_ata, a collection offer opens it for the nft at borrow time
+        if !collateral_is_collection {
+            anchor_spl::associated_token::create(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                anchor_spl::associated_token::Create {
+                    payer: self.lender.to_account_info(),
+                    associated_token: loan_collateral_ata.to_account_info(),
+                    authority: self.loan.to_account_info(),
 
-                mint: collateral_mint.to_account_info(),
-                system_program: self.system_program.to_account_info(),
-                token_program: self.token_program.to_account_info(),
-            }
-        ))?;
+                    mint: collateral_mint.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: self.collateral_token_program.to_account_info(),
+                }
+            ))?;
+        }
 
-        // transfer loan_mint lender to pda
-        token_transfer_user(
-            lender_loan_ata.clone(),
-            &self.lender, 
-            loan_mint_ata.clone(), 
-            &self.token_program, 
-            loan_amount
-        )?;
-        Ok(())
+        if is_native_mint(&loan_mint.key()) {
+            // wrap lender sol into the loan_mint pda ata
+            wrap_sol_from_user(
+                &self.lender,
+                loan_mint_ata.clone(),
+                &self.system_program,
+                &self.loan_token_program,
+                loan_amount
+            )?;
+        } else {
+            // transfer loan_mint lender to pda
+            token_transfer_user(
+                lender_loan_ata.clone(),
+                &self.lender, 
+                loan_mint_ata.clone(), 
+                &self.loan_mint,
+                &self.loan_token_program, 
+                loan_amount
+            )?;
+        }
+
+        let loan = &self.loan;
+        Ok(LoanCreated {
+            loan: loan.key(),
+            lender: loan.lender,
+            loan_mint: loan.loan_mint,
+            collateral_mint: loan.collateral_mint,
+            collateral_collection: loan.collateral_collection,
+            loan_amount: loan.loan_amount,
+            interest_rate: loan.interest_rate,
+            duration: loan.duration,
+            collateral_amount: loan.collateral_amount,
+            max_extension: loan.max_extension,
+            min_collateral_ratio_bps: loan.price_terms.min_collateral_ratio_bps,
+            expire_date: loan.expire_date,
+            timestamp: loan.create_date,
+        })
     }
 }
\ No newline at end of file
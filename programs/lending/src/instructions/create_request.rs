@@ -0,0 +1,212 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::{ASSET, BORROWER_PAGE, CONFIG, LOAN, PAUSE_CREATE, PROFILE},
+    errors::AgioError,
+    events::LoanRequested,
+    interest,
+    portfolio,
+    state::{AssetConfig, Config, Loan, LoanPage, LoanStatus, PortfolioRole, UserProfile},
+    utils::{is_native_mint, received_amount, token_transfer_user, validate_mint, wrap_sol_from_user}
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenInterface},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateRequest<'info> {
+    #[account(
+        seeds = [CONFIG.as_bytes()],
+        bump,
+        constraint = !global_config.is_paused(PAUSE_CREATE) @AgioError::Paused,
+    )]
+    global_config: Box<Account<'info, Config>>,
+
+    #[account(
+        seeds = [ASSET.as_bytes(), loan_mint.key().as_ref()],
+        bump,
+    )]
+    loan_asset: Box<Account<'info, AssetConfig>>,
+
+    #[account(
+        seeds = [ASSET.as_bytes(), collateral_mint.key().as_ref()],
+        bump,
+    )]
+    collateral_asset: Box<Account<'info, AssetConfig>>,
+
+    //  same layout as an offer, derived from the borrower until a lender fills it
+    #[account(
+        init,
+        payer = borrower,
+        space = 8 + Loan::INIT_SPACE,
+        seeds = [LOAN.as_bytes(), &borrower.key().to_bytes(), &loan_key.key().to_bytes()],
+        bump,
+    )]
+    request: Account<'info, Loan>,
+
+    #[account(
+        mint::token_program = collateral_token_program,
+    )]
+    collateral_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mint::token_program = loan_token_program,
+    )]
+    loan_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [
+            request.key().as_ref(),
+            collateral_token_program.key().as_ref(),
+            collateral_mint.key().as_ref(),
+        ],
+        seeds::program = anchor_spl::associated_token::ID,
+        bump,
+    )]
+    request_collateral_ata: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            borrower.key().as_ref(),
+            collateral_token_program.key().as_ref(),
+            collateral_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::associated_token::ID
+    )]
+    borrower_collateral_ata: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+
+    pub loan_key: InterfaceAccount<'info, Mint>,
+
+    //  portfolio index, the page is the one the next listed loan lands in
+    #[account(
+        init_if_needed,
+        payer = borrower,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [PROFILE.as_bytes(), borrower.key().as_ref()],
+        bump,
+    )]
+    borrower_profile: Box<Account<'info, UserProfile>>,
+
+    #[account(
+        init_if_needed,
+        payer = borrower,
+        space = 8 + LoanPage::INIT_SPACE,
+        seeds = [BORROWER_PAGE.as_bytes(), borrower.key().as_ref(), &borrower_profile.as_borrower.next_page_index().to_le_bytes()],
+        bump,
+    )]
+    borrower_page: Box<Account<'info, LoanPage>>,
+
+    pub system_program: Program<'info, System>,
+    pub loan_token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> CreateRequest<'info> {
+    pub fn process(
+        &mut self,
+        loan_amount: u64,
+        max_interest_rate: u64,
+        duration: u64,
+        collateral_amount: u64,
+    ) -> Result<LoanRequested> {
+
+        let global_config = &self.global_config;
+        let request_collateral_ata = &self.request_collateral_ata;
+        let collateral_mint = &self.collateral_mint;
+        let loan_mint = &self.loan_mint;
+
+        validate_mint(loan_mint)?;
+        validate_mint(collateral_mint)?;
+        self.loan_asset.require_loan(loan_amount)?;
+        self.collateral_asset.require_collateral()?;
+
+        let request = &mut self.request;
+
+        request.version = Loan::VERSION;
+        request.lender = Pubkey::default(); // No lender yet
+        request.borrower = self.borrower.key();
+
+        request.loan_mint = loan_mint.key();
+        request.loan_amount = loan_amount;
+        // the highest rate the borrower accepts, a lender may fill below it
+        request.interest_rate = max_interest_rate;
+        request.duration = interest::term_seconds(duration)?;
+        request.collateral_mint = collateral_mint.key();
+        // the vault only holds what is left after the mint's transfer fee
+        request.collateral_amount = received_amount(collateral_mint, collateral_amount)?;
+
+        let expire_duration = interest::days_to_seconds(global_config.expire_duration.into())?;
+
+        request.create_date = Clock::get()?.unix_timestamp;
+        request.expire_date = request.create_date
+            .checked_add(expire_duration)
+            .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+        request.status = LoanStatus::Requested;
+        // fill_request keeps this slot for the loan, nothing counts towards the lifetime totals until then
+        request.borrower_slot = portfolio::list(
+            PortfolioRole::Borrower,
+            request.key(),
+            request.borrower,
+            &mut self.borrower_profile,
+            &mut self.borrower_page,
+        )?;
+
+        // create request collateral_mint ata
+        anchor_spl::associated_token::create(CpiContext::new(
+            self.associated_token_program.to_account_info(),
+            anchor_spl::associated_token::Create {
+                payer: self.borrower.to_account_info(),
+                associated_token: request_collateral_ata.to_account_info(),
+                authority: self.request.to_account_info(),
+
+                mint: collateral_mint.to_account_info(),
+                system_program: self.system_program.to_account_info(),
+                token_program: self.collateral_token_program.to_account_info(),
+            }
+        ))?;
+
+        if is_native_mint(&collateral_mint.key()) {
+            // wrap borrower sol into the collateral_mint pda ata
+            wrap_sol_from_user(
+                &self.borrower,
+                request_collateral_ata.clone(),
+                &self.system_program,
+                &self.collateral_token_program,
+                collateral_amount
+            )?;
+        } else {
+            // transfer collateral_mint borrower to pda
+            token_transfer_user(
+                self.borrower_collateral_ata.clone(),
+                &self.borrower,
+                request_collateral_ata.clone(),
+                collateral_mint,
+                &self.collateral_token_program,
+                collateral_amount
+            )?;
+        }
+
+        let request = &self.request;
+        Ok(LoanRequested {
+            request: request.key(),
+            borrower: request.borrower,
+            loan_mint: request.loan_mint,
+            collateral_mint: request.collateral_mint,
+            loan_amount: request.loan_amount,
+            max_interest_rate: request.interest_rate,
+            duration: request.duration,
+            collateral_amount: request.collateral_amount,
+            expire_date: request.expire_date,
+            timestamp: request.create_date,
+        })
+    }
+}
@@ -0,0 +1,226 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenInterface}};
+
+use crate::{constants::{CONFIG, LOAN}, errors::AgioError, events::LoanExtended, fees, interest, state::{Config, Loan}, utils::{is_native_mint, sol_transfer_from_user, token_transfer_user}};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExtendLoan<'info> {
+
+    #[account(
+        seeds = [CONFIG.as_bytes()],
+        bump,
+    )]
+    global_config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [LOAN.as_bytes(), &lender.key().to_bytes(), &loan_key.key().to_bytes()],
+        bump,
+    )]
+    loan: Account<'info, Loan>,
+
+    #[account(
+        constraint = loan_mint.key() == loan.loan_mint @AgioError::InvalidLoanMint,
+        mint::token_program = loan_token_program,
+    )]
+    loan_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [
+            lender.key().as_ref(),
+            loan_token_program.key().as_ref(),
+            loan_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::associated_token::ID
+    )]
+    lender_loan_ata: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            borrower.key().as_ref(),
+            loan_token_program.key().as_ref(),
+            loan_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::associated_token::ID
+    )]
+    borrower_loan_ata: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            team_wallet.key().as_ref(),
+            loan_token_program.key().as_ref(),
+            loan_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::associated_token::ID
+    )]
+    team_loan_ata: AccountInfo<'info>,
+
+    /// CHECK: should be same with the address in the global_config
+    #[account(
+        mut,
+        constraint = team_wallet.key() == global_config.team_wallet @AgioError::IncorrectTeamWallet
+    )]
+    pub team_wallet: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = borrower.key() == loan.borrower @AgioError::IncorrectAuthority,
+    )]
+    pub borrower: Signer<'info>,
+
+    //  co-signs to accept any terms, otherwise only the offer's max_extension applies
+    #[account(mut)]
+    pub lender: AccountInfo<'info>,
+
+    pub loan_key: InterfaceAccount<'info, Mint>,
+
+    pub system_program: Program<'info, System>,
+    pub loan_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> ExtendLoan<'info> {
+    pub fn process(
+        &mut self,
+        duration: u64,
+        interest_rate: u64,
+    ) -> Result<LoanExtended> {
+        let clock = Clock::get()?;
+        let current_time = clock.unix_timestamp;
+
+        let global_config = &self.global_config;
+        let loan = &mut self.loan;
+        let lender_loan_ata = &self.lender_loan_ata;
+        let team_loan_ata = &self.team_loan_ata;
+
+        loan.status.require_active()?;
+
+        let lender_signed = self.lender.is_signer;
+        let previous_maturity = loan.maturity()?;
+
+        // the new term runs from now
+        let maturity = interest::term_seconds(duration)?
+            .checked_add(current_time)
+            .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+
+        // without the lender, the borrower may only spend the pre-approved extension,
+        // before maturity and at no lower rate
+        if !lender_signed {
+            require!(current_time <= previous_maturity, AgioError::LoanPastMaturity);
+            require!(interest_rate >= loan.interest_rate, AgioError::InvalidExtension);
+
+            let extension = maturity
+                .checked_sub(previous_maturity)
+                .ok_or(AgioError::OverflowOrUnderflowOccurred)?
+                .max(0);
+            require!(extension <= loan.max_extension, AgioError::ExtensionNotApproved);
+            loan.max_extension -= extension;
+        }
+
+        let native_loan = is_native_mint(&loan.loan_mint);
+
+        // create lender loan_mint ata if it doesn't exit
+        if !native_loan && lender_loan_ata.data_is_empty() {
+            anchor_spl::associated_token::create(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                anchor_spl::associated_token::Create {
+                    payer: self.borrower.to_account_info(),
+                    associated_token: lender_loan_ata.to_account_info(),
+                    authority: self.lender.to_account_info(),
+
+                    mint: self.loan_mint.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: self.loan_token_program.to_account_info(),
+                }
+            ))?;
+        }
+
+        // create team_wallet loan_mint ata if it doesn't exit
+        if !native_loan && team_loan_ata.data_is_empty() {
+            anchor_spl::associated_token::create(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                anchor_spl::associated_token::Create {
+                    payer: self.borrower.to_account_info(),
+                    associated_token: team_loan_ata.to_account_info(),
+                    authority: self.team_wallet.to_account_info(),
+
+                    mint: self.loan_mint.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: self.loan_token_program.to_account_info(),
+                }
+            ))?;
+        }
+
+        // settle the interest accrued under the old terms
+        let interest = loan.interest_owed(current_time)?;
+
+        // team's cut is taken out of the interest, never the principal
+        let (lender_interest, fee_amount) = fees::split(interest, fees::lend_fee(interest, global_config)?)?;
+
+        if native_loan {
+            // sol transfer interest borrower to lender and fee to team_wallet
+            if lender_interest > 0 {
+                sol_transfer_from_user(&self.borrower, self.lender.clone(), &self.system_program, lender_interest)?;
+            }
+            if fee_amount > 0 {
+                sol_transfer_from_user(&self.borrower, self.team_wallet.clone(), &self.system_program, fee_amount)?;
+            }
+        } else {
+            // transfer interest borrower to lender and fee to team_wallet
+            if lender_interest > 0 {
+                token_transfer_user(
+                    self.borrower_loan_ata.clone(),
+                    &self.borrower,
+                    lender_loan_ata.clone(),
+                    &self.loan_mint,
+                    &self.loan_token_program,
+                    lender_interest
+                )?;
+            }
+            if fee_amount > 0 {
+                token_transfer_user(
+                    self.borrower_loan_ata.clone(),
+                    &self.borrower,
+                    team_loan_ata.clone(),
+                    &self.loan_mint,
+                    &self.loan_token_program,
+                    fee_amount
+                )?;
+            }
+        }
+
+        let previous_duration = loan.duration;
+        let previous_interest_rate = loan.interest_rate;
+
+        // maturity stays borrow_date + duration, accrual restarts now under the new rate
+        loan.duration = maturity
+            .checked_sub(loan.borrow_date)
+            .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+        loan.interest_rate = interest_rate;
+        loan.interest_paid = loan.interest_paid
+            .checked_add(interest)
+            .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+        loan.last_payment_ts = current_time;
+
+        Ok(LoanExtended {
+            loan: loan.key(),
+            lender: loan.lender,
+            borrower: loan.borrower,
+            previous_duration,
+            duration: loan.duration,
+            previous_interest_rate,
+            interest_rate: loan.interest_rate,
+            interest_paid: interest,
+            fee_amount,
+            lender_signed,
+            timestamp: current_time,
+        })
+    }
+}
@@ -0,0 +1,379 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface}};
+
+use crate::{constants::{ASSET, BORROWER_PAGE, CONFIG, LENDER_PAGE, LOAN, PAUSE_BORROW, PROFILE}, errors::AgioError, events::RequestFilled, fees, portfolio, seeds::loan_signer_seeds, state::{AssetConfig, Config, Loan, LoanPage, LoanStatus, PortfolioRole, UserProfile}, utils::{is_native_mint, received_amount, sol_transfer_from_user, token_close_with_signer, token_transfer_user, token_transfer_with_signer}};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FillRequest<'info> {
+
+    #[account(
+        seeds = [CONFIG.as_bytes()],
+        bump,
+        constraint = !global_config.is_paused(PAUSE_BORROW) @AgioError::Paused,
+    )]
+    global_config: Box<Account<'info, Config>>,
+
+    //  the borrower's request, closed once its terms move to the loan
+    #[account(
+        mut,
+        close = borrower,
+        seeds = [LOAN.as_bytes(), &borrower.key().to_bytes(), &loan_key.key().to_bytes()],
+        bump,
+    )]
+    request: Box<Account<'info, Loan>>,
+
+    //  the active loan at the canonical address, so every later instruction treats it like a borrowed offer
+    #[account(
+        init,
+        payer = lender,
+        space = 8 + Loan::INIT_SPACE,
+        seeds = [LOAN.as_bytes(), &lender.key().to_bytes(), &loan_key.key().to_bytes()],
+        bump,
+    )]
+    loan: Box<Account<'info, Loan>>,
+
+    #[account(
+        constraint = loan_mint.key() == request.loan_mint @AgioError::InvalidLoanMint,
+        mint::token_program = loan_token_program,
+    )]
+    pub loan_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [ASSET.as_bytes(), loan_mint.key().as_ref()],
+        bump,
+    )]
+    loan_asset: Box<Account<'info, AssetConfig>>,
+
+    #[account(
+        seeds = [ASSET.as_bytes(), request.collateral_mint.as_ref()],
+        bump,
+    )]
+    collateral_asset: Box<Account<'info, AssetConfig>>,
+
+    #[account(
+        mut,
+        constraint = collateral_mint.key() == request.collateral_mint @AgioError::InvalidCollateral,
+        mint::token_program = collateral_token_program,
+    )]
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [
+            request.key().as_ref(),
+            collateral_token_program.key().as_ref(),
+            collateral_mint.key().as_ref(),
+        ],
+        seeds::program = anchor_spl::associated_token::ID,
+        bump,
+    )]
+    request_collateral_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [
+            loan.key().as_ref(),
+            collateral_token_program.key().as_ref(),
+            collateral_mint.key().as_ref(),
+        ],
+        seeds::program = anchor_spl::associated_token::ID,
+        bump,
+    )]
+    loan_collateral_ata: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            lender.key().as_ref(),
+            loan_token_program.key().as_ref(),
+            loan_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::associated_token::ID
+    )]
+    lender_loan_ata: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            borrower.key().as_ref(),
+            loan_token_program.key().as_ref(),
+            loan_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::associated_token::ID
+    )]
+    borrower_loan_ata: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            team_wallet.key().as_ref(),
+            loan_token_program.key().as_ref(),
+            loan_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::associated_token::ID
+    )]
+    team_loan_ata: AccountInfo<'info>,
+
+    /// CHECK: should be same with the address in the global_config
+    #[account(
+        mut,
+        constraint = team_wallet.key() == global_config.team_wallet @AgioError::IncorrectTeamWallet
+    )]
+    pub team_wallet: AccountInfo<'info>,
+
+    //  receives the principal and the rent of the request and its vault
+    #[account(
+        mut,
+        constraint = borrower.key() == request.borrower @AgioError::IncorrectAuthority,
+        constraint = borrower.key() != lender.key() @AgioError::LenderIsBorrower,
+    )]
+    pub borrower: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub lender: Signer<'info>,
+
+    pub loan_key: Box<InterfaceAccount<'info, Mint>>,
+
+    //  portfolio index, the page is the one the next listed loan lands in
+    #[account(
+        init_if_needed,
+        payer = lender,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [PROFILE.as_bytes(), lender.key().as_ref()],
+        bump,
+    )]
+    lender_profile: Box<Account<'info, UserProfile>>,
+
+    #[account(
+        init_if_needed,
+        payer = lender,
+        space = 8 + LoanPage::INIT_SPACE,
+        seeds = [LENDER_PAGE.as_bytes(), lender.key().as_ref(), &lender_profile.as_lender.next_page_index().to_le_bytes()],
+        bump,
+    )]
+    lender_page: Box<Account<'info, LoanPage>>,
+
+    #[account(
+        init_if_needed,
+        payer = lender,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [PROFILE.as_bytes(), borrower.key().as_ref()],
+        bump,
+    )]
+    borrower_profile: Box<Account<'info, UserProfile>>,
+
+    //  the page already listing the request, created only for a request made before requests were listed
+    #[account(
+        init_if_needed,
+        payer = lender,
+        space = 8 + LoanPage::INIT_SPACE,
+        seeds = [BORROWER_PAGE.as_bytes(), borrower.key().as_ref(), &request.request_page_index(&borrower_profile.as_borrower).to_le_bytes()],
+        bump,
+    )]
+    borrower_page: Box<Account<'info, LoanPage>>,
+
+    pub system_program: Program<'info, System>,
+    pub loan_token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> FillRequest<'info> {
+    pub fn process(
+        &mut self,
+        interest_rate: u64,
+        request_bump: u8,
+    ) -> Result<RequestFilled> {
+        let clock = Clock::get()?;
+        let current_time = clock.unix_timestamp;
+
+        let global_config = &self.global_config;
+        let request = &mut self.request;
+        let request_collateral_ata = &self.request_collateral_ata;
+        let loan_collateral_ata = &self.loan_collateral_ata;
+        let lender_loan_ata = &self.lender_loan_ata;
+        let borrower_loan_ata = &self.borrower_loan_ata;
+        let team_loan_ata = &self.team_loan_ata;
+
+        require!(request.status == LoanStatus::Requested, AgioError::NotARequest);
+        require!(request.expire_date > current_time, AgioError::AlreadyExpired);
+        require!(interest_rate <= request.interest_rate, AgioError::InterestRateTooHigh);
+        // the registry may have changed since the request was made
+        self.loan_asset.require_loan(request.loan_amount)?;
+        self.collateral_asset.require_collateral()?;
+        request.transition(LoanStatus::Active)?;
+
+        let native_loan = is_native_mint(&request.loan_mint);
+
+        //  create borrower loan_mint ata, if it doesn't exit
+        if !native_loan && borrower_loan_ata.data_is_empty() {
+            anchor_spl::associated_token::create(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                anchor_spl::associated_token::Create {
+                    payer: self.lender.to_account_info(),
+                    associated_token: borrower_loan_ata.to_account_info(),
+                    authority: self.borrower.to_account_info(),
+
+                    mint: self.loan_mint.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: self.loan_token_program.to_account_info(),
+                }
+            ))?;
+        }
+
+        // create team_wallet loan_mint ata if it doesn't exit
+        if !native_loan && team_loan_ata.data_is_empty() {
+            anchor_spl::associated_token::create(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                anchor_spl::associated_token::Create {
+                    payer: self.lender.to_account_info(),
+                    associated_token: team_loan_ata.to_account_info(),
+                    authority: self.team_wallet.to_account_info(),
+
+                    mint: self.loan_mint.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: self.loan_token_program.to_account_info(),
+                }
+            ))?;
+        }
+
+        // create loan collateral_mint ata
+        anchor_spl::associated_token::create(CpiContext::new(
+            self.associated_token_program.to_account_info(),
+            anchor_spl::associated_token::Create {
+                payer: self.lender.to_account_info(),
+                associated_token: loan_collateral_ata.to_account_info(),
+                authority: self.loan.to_account_info(),
+
+                mint: self.collateral_mint.to_account_info(),
+                system_program: self.system_program.to_account_info(),
+                token_program: self.collateral_token_program.to_account_info(),
+            }
+        ))?;
+
+        // the borrower is not signing, so the borrow fee is always withheld from the principal
+        let loan_amount = request.loan_amount;
+        let fee_amount = fees::borrow_fee(loan_amount, global_config, &self.loan_asset)?;
+        let disbursed_amount = fees::disbursed(loan_amount, fee_amount, false)?;
+
+        if native_loan {
+            // sol transfer principal lender to borrower and fee to team_wallet
+            sol_transfer_from_user(&self.lender, self.borrower.clone(), &self.system_program, disbursed_amount)?;
+            if fee_amount > 0 {
+                sol_transfer_from_user(&self.lender, self.team_wallet.clone(), &self.system_program, fee_amount)?;
+            }
+        } else {
+            // transfer principal lender to borrower and fee to team_wallet
+            token_transfer_user(
+                lender_loan_ata.clone(),
+                &self.lender,
+                borrower_loan_ata.clone(),
+                &self.loan_mint,
+                &self.loan_token_program,
+                disbursed_amount
+            )?;
+            if fee_amount > 0 {
+                token_transfer_user(
+                    lender_loan_ata.clone(),
+                    &self.lender,
+                    team_loan_ata.clone(),
+                    &self.loan_mint,
+                    &self.loan_token_program,
+                    fee_amount
+                )?;
+            }
+        }
+
+        let borrower_key = request.borrower;
+        let loan_key = self.loan_key.key();
+        let bump = [request_bump];
+        let signer_seeds = loan_signer_seeds(&borrower_key, &loan_key, &bump);
+
+        // move the collateral from the request vault into the loan vault, wsol moves as tokens
+        let moved_amount = request_collateral_ata.amount;
+        token_transfer_with_signer(
+            request_collateral_ata.to_account_info(),
+            request.to_account_info(),
+            loan_collateral_ata.clone(),
+            &self.collateral_mint,
+            &self.collateral_token_program,
+            &[&signer_seeds[..]],
+            moved_amount
+        )?;
+
+        // close request vault and refund rent to borrower
+        token_close_with_signer(
+            request_collateral_ata.to_account_info(),
+            self.borrower.to_account_info(),
+            request.to_account_info(),
+            &self.collateral_mint,
+            &self.collateral_token_program,
+            &[&signer_seeds[..]],
+        )?;
+
+        let loan = &mut self.loan;
+
+        loan.version = Loan::VERSION;
+        loan.lender = self.lender.key();
+        loan.borrower = request.borrower;
+
+        loan.loan_mint = request.loan_mint;
+        loan.loan_amount = loan_amount;
+        loan.interest_rate = interest_rate;
+        loan.duration = request.duration;
+        loan.collateral_mint = request.collateral_mint;
+        // the vault only holds what is left after the mint's transfer fee
+        loan.collateral_amount = received_amount(&self.collateral_mint, moved_amount)?;
+
+        loan.create_date = request.create_date;
+        loan.expire_date = request.expire_date;
+        loan.borrow_date = current_time;
+        loan.status = request.status;
+
+        loan.principal_outstanding = loan_amount;
+        loan.interest_paid = 0;
+        loan.last_payment_ts = current_time;
+
+        // the borrower is not signing, so the lender pays for any profile or page the borrower lacks
+        loan.lender_slot = portfolio::list(
+            PortfolioRole::Lender,
+            loan.key(),
+            loan.lender,
+            &mut self.lender_profile,
+            &mut self.lender_page,
+        )?;
+        // the request is already listed for the borrower, its slot now points at the loan
+        if request.borrower_slot == 0 {
+            loan.borrower_slot = portfolio::list(
+                PortfolioRole::Borrower,
+                loan.key(),
+                loan.borrower,
+                &mut self.borrower_profile,
+                &mut self.borrower_page,
+            )?;
+        } else {
+            portfolio::relist(request.key(), loan.key(), request.borrower_slot, &mut self.borrower_page)?;
+            loan.borrower_slot = request.borrower_slot;
+        }
+        portfolio::record(PortfolioRole::Lender, loan_amount, &mut self.lender_profile)?;
+        portfolio::record(PortfolioRole::Borrower, loan_amount, &mut self.borrower_profile)?;
+
+        Ok(RequestFilled {
+            request: request.key(),
+            loan: loan.key(),
+            lender: loan.lender,
+            borrower: loan.borrower,
+            loan_mint: loan.loan_mint,
+            collateral_mint: loan.collateral_mint,
+            loan_amount: loan.loan_amount,
+            interest_rate: loan.interest_rate,
+            collateral_amount: loan.collateral_amount,
+            fee_amount,
+            timestamp: current_time,
+        })
+    }
+}
@@ -0,0 +1,115 @@
+use crate::{
+    constants::{CONFIG, GLOBAL}, errors::AgioError, events::ConfigUpdated, state::Config, utils::sol_transfer_from_user
+};
+use anchor_lang::{prelude::*, solana_program::bpf_loader_upgradeable, system_program};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{Mint, Token, TokenAccount},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    //  becomes the authority, only the program's upgrade authority may claim it
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(payer.key()) @AgioError::IncorrectAuthority,
+    )]
+    program_data: Box<Account<'info, ProgramData>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [CONFIG.as_bytes()],
+        bump,
+    )]
+    global_config: Box<Account<'info, Config>>,
+
+    /// CHECK: global vault pda which stores SOL
+    #[account(
+        mut,
+        seeds = [GLOBAL.as_bytes()],
+        bump,
+    )]
+    pub global_vault: AccountInfo<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = native_mint,
+        associated_token::authority = global_vault
+    )]
+    global_wsol_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        address = spl_token::native_mint::ID
+    )]
+    native_mint: Box<Account<'info, Mint>>,
+
+    #[account(address = system_program::ID)]
+    system_program: Program<'info, System>,
+
+    token_program: Program<'info, Token>,
+
+    associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> InitializeConfig<'info> {
+    pub fn process(
+        &mut self,
+        team_wallet: Pubkey,
+        lend_fee: u64,
+        borrow_fee: u64,
+        liquidation_fee: u64,
+        max_fee: u64,
+        expire_duration: u8,
+    ) -> Result<ConfigUpdated> {
+        let global_config = &mut self.global_config;
+
+        //  payer becomes the authority, handover goes through nominate_authority
+        global_config.version = Config::VERSION;
+        global_config.authority = self.payer.key();
+        global_config.pending_authority = Pubkey::default();
+        global_config.team_wallet = team_wallet;
+        global_config.lend_fee = lend_fee;
+        global_config.borrow_fee = borrow_fee;
+        global_config.liquidation_fee = liquidation_fee;
+        global_config.borrow_fee_on_top = false;
+        global_config.max_fee = max_fee;
+        global_config.expire_duration = expire_duration;
+        //  liquidation stays opt-in per loan until set_liquidation_params
+        global_config.liquidation_threshold_bps = 0;
+        global_config.liquidation_bonus_bps = 0;
+        global_config.guardian = Pubkey::default();
+        global_config.pause_flags = 0;
+        global_config.validate()?;
+
+        //  initialize global vault if needed
+        if self.global_vault.lamports() == 0 {
+            sol_transfer_from_user(
+                &self.payer,
+                self.global_vault.clone(),
+                &self.system_program,
+                890880,
+            )?;
+        }
+
+        let global_config = &self.global_config;
+        Ok(ConfigUpdated {
+            authority: global_config.authority,
+            team_wallet: global_config.team_wallet,
+            lend_fee: global_config.lend_fee,
+            borrow_fee: global_config.borrow_fee,
+            liquidation_fee: global_config.liquidation_fee,
+            max_fee: global_config.max_fee,
+            expire_duration: global_config.expire_duration,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
@@ -0,0 +1,438 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface}};
+
+use crate::{constants::{BORROWER_PAGE, CONFIG, LENDER_PAGE, LOAN, PROFILE}, errors::AgioError, events::LoanLiquidated, fees, portfolio, oracle, seeds::loan_signer_seeds, state::{Config, Loan, LoanPage, LoanStatus, PortfolioRole, UserProfile}, utils::{is_native_mint, sol_transfer_from_program_account, sol_transfer_from_user, token_close_if_empty_with_signer, token_close_with_signer, token_transfer_user, token_transfer_with_signer}};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Liquidate<'info> {
+
+    #[account(
+        seeds = [CONFIG.as_bytes()],
+        bump,
+    )]
+    global_config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        close = lender,
+        seeds = [LOAN.as_bytes(), &lender.key().to_bytes(), &loan_key.key().to_bytes()],
+        bump,
+    )]
+    loan: Box<Account<'info, Loan>>,
+
+    #[account(
+        mut,
+        constraint = loan_mint.key() == loan.loan_mint @AgioError::InvalidLoanMint,
+        mint::token_program = loan_token_program,
+    )]
+    pub loan_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = collateral_mint.key() == loan.collateral_mint @AgioError::InvalidCollateral,
+        mint::token_program = collateral_token_program,
+    )]
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [
+            loan.key().as_ref(),
+            collateral_token_program.key().as_ref(),
+            collateral_mint.key().as_ref(),
+        ],
+        seeds::program = anchor_spl::associated_token::ID,
+        bump,
+    )]
+    loan_collateral_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [
+            loan.key().as_ref(),
+            loan_token_program.key().as_ref(),
+            loan_mint.key().as_ref(),
+        ],
+        seeds::program = anchor_spl::associated_token::ID,
+        bump,
+    )]
+    loan_mint_ata: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            liquidator.key().as_ref(),
+            loan_token_program.key().as_ref(),
+            loan_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::associated_token::ID
+    )]
+    liquidator_loan_ata: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            liquidator.key().as_ref(),
+            collateral_token_program.key().as_ref(),
+            collateral_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::associated_token::ID
+    )]
+    liquidator_collateral_ata: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            lender.key().as_ref(),
+            loan_token_program.key().as_ref(),
+            loan_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::associated_token::ID
+    )]
+    lender_loan_ata: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            borrower.key().as_ref(),
+            collateral_token_program.key().as_ref(),
+            collateral_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::associated_token::ID
+    )]
+    borrower_collateral_ata: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            team_wallet.key().as_ref(),
+            loan_token_program.key().as_ref(),
+            loan_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::associated_token::ID
+    )]
+    team_loan_ata: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            team_wallet.key().as_ref(),
+            collateral_token_program.key().as_ref(),
+            collateral_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::associated_token::ID
+    )]
+    team_collateral_ata: AccountInfo<'info>,
+
+    /// CHECK: should be same with the address in the global_config
+    #[account(
+        mut,
+        constraint = team_wallet.key() == global_config.team_wallet @AgioError::IncorrectTeamWallet,
+    )]
+    pub team_wallet: AccountInfo<'info>,
+
+    //  receives the repaid debt and the rent of the loan and its vaults, except a collection offer's collateral vault
+    #[account(mut)]
+    pub lender: AccountInfo<'info>,
+
+    //  receives whatever collateral is left after the liquidator and the protocol are paid
+    #[account(
+        mut,
+        constraint = borrower.key() == loan.borrower @AgioError::IncorrectAuthority,
+    )]
+    pub borrower: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub liquidator: Signer<'info>,
+
+    pub loan_key: Box<InterfaceAccount<'info, Mint>>,
+
+    //  pyth PriceUpdateV2 accounts for the loan's feeds
+    pub loan_price_update: AccountInfo<'info>,
+    pub collateral_price_update: AccountInfo<'info>,
+
+    //  portfolio index, only required for loans listed when they were created
+    #[account(
+        mut,
+        seeds = [PROFILE.as_bytes(), lender.key().as_ref()],
+        bump,
+    )]
+    lender_profile: Option<Box<Account<'info, UserProfile>>>,
+
+    #[account(
+        mut,
+        seeds = [LENDER_PAGE.as_bytes(), lender.key().as_ref(), &loan.lender_page_index().to_le_bytes()],
+        bump,
+    )]
+    lender_page: Option<Box<Account<'info, LoanPage>>>,
+
+    #[account(
+        mut,
+        seeds = [PROFILE.as_bytes(), borrower.key().as_ref()],
+        bump,
+    )]
+    borrower_profile: Option<Box<Account<'info, UserProfile>>>,
+
+    #[account(
+        mut,
+        seeds = [BORROWER_PAGE.as_bytes(), borrower.key().as_ref(), &loan.borrower_page_index().to_le_bytes()],
+        bump,
+    )]
+    borrower_page: Option<Box<Account<'info, LoanPage>>>,
+
+    pub system_program: Program<'info, System>,
+    pub loan_token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> Liquidate<'info> {
+    pub fn process(
+        &mut self,
+        loan_bump: u8
+    ) -> Result<LoanLiquidated> {
+        let clock = Clock::get()?;
+        let current_time = clock.unix_timestamp;
+
+        let global_config = &self.global_config;
+        let loan = &mut self.loan;
+        let loan_collateral_ata = &self.loan_collateral_ata;
+
+        loan.transition(LoanStatus::Liquidated)?;
+
+        portfolio::unlist(
+            PortfolioRole::Lender,
+            loan.key(),
+            loan.lender_slot,
+            self.lender_profile.as_mut(),
+            self.lender_page.as_mut(),
+            &self.lender.to_account_info(),
+        )?;
+        portfolio::unlist(
+            PortfolioRole::Borrower,
+            loan.key(),
+            loan.borrower_slot,
+            self.borrower_profile.as_mut(),
+            self.borrower_page.as_mut(),
+            &self.borrower.to_account_info(),
+        )?;
+
+        let threshold_bps = loan.price_terms.liquidation_threshold_bps(global_config);
+        require!(threshold_bps > 0 && loan.price_terms.has_feeds(), AgioError::LiquidationDisabled);
+
+        let loan_price = oracle::load_price(&self.loan_price_update, &loan.price_terms.loan_price_feed, current_time)?;
+        let collateral_price = oracle::load_price(&self.collateral_price_update, &loan.price_terms.collateral_price_feed, current_time)?;
+
+        // debt is what repay_loan would charge right now
+        let principal = loan.principal_outstanding;
+        let interest = loan.interest_owed(current_time)?;
+        let debt = principal
+            .checked_add(interest)
+            .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+
+        let collateral_balance = loan_collateral_ata.amount;
+        require!(
+            oracle::exceeds_loan_to_value(
+                debt,
+                &loan_price,
+                self.loan_mint.decimals,
+                collateral_balance,
+                &collateral_price,
+                self.collateral_mint.decimals,
+                threshold_bps,
+            )?,
+            AgioError::LoanHealthy
+        );
+
+        // team's cut of the interest, exactly as on repay
+        let (lender_interest, lend_fee_amount) = fees::split(interest, fees::lend_fee(interest, global_config)?)?;
+        let lender_amount = principal
+            .checked_add(lender_interest)
+            .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+
+        // liquidator takes the debt's worth of collateral plus the bonus, capped at what the vault holds
+        let debt_in_collateral = oracle::convert_amount(
+            debt,
+            &loan_price,
+            self.loan_mint.decimals,
+            &collateral_price,
+            self.collateral_mint.decimals,
+        )?;
+        let seized_amount = debt_in_collateral
+            .checked_add(fees::fee_bps(debt_in_collateral, global_config.liquidation_bonus_bps)?)
+            .ok_or(AgioError::OverflowOrUnderflowOccurred)?
+            .min(collateral_balance);
+        let (liquidator_amount, liquidation_fee_amount) = fees::seized(seized_amount, global_config)?;
+        let returned_amount = collateral_balance
+            .checked_sub(seized_amount)
+            .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+
+        let native_loan = is_native_mint(&loan.loan_mint);
+        let native_collateral = is_native_mint(&loan.collateral_mint);
+
+        if !native_loan {
+            // create lender and team_wallet loan_mint ata if they don't exit
+            for (associated_token, authority) in [
+                (&self.lender_loan_ata, &self.lender),
+                (&self.team_loan_ata, &self.team_wallet),
+            ] {
+                if associated_token.data_is_empty() {
+                    anchor_spl::associated_token::create(CpiContext::new(
+                        self.associated_token_program.to_account_info(),
+                        anchor_spl::associated_token::Create {
+                            payer: self.liquidator.to_account_info(),
+                            associated_token: associated_token.to_account_info(),
+                            authority: authority.to_account_info(),
+
+                            mint: self.loan_mint.to_account_info(),
+                            system_program: self.system_program.to_account_info(),
+                            token_program: self.loan_token_program.to_account_info(),
+                        }
+                    ))?;
+                }
+            }
+        }
+
+        if !native_collateral {
+            // create liquidator, borrower and team_wallet collateral_mint ata if they don't exit
+            for (associated_token, authority) in [
+                (&self.liquidator_collateral_ata, &self.liquidator.to_account_info()),
+                (&self.borrower_collateral_ata, &self.borrower),
+                (&self.team_collateral_ata, &self.team_wallet),
+            ] {
+                if associated_token.data_is_empty() {
+                    anchor_spl::associated_token::create(CpiContext::new(
+                        self.associated_token_program.to_account_info(),
+                        anchor_spl::associated_token::Create {
+                            payer: self.liquidator.to_account_info(),
+                            associated_token: associated_token.to_account_info(),
+                            authority: authority.to_account_info(),
+
+                            mint: self.collateral_mint.to_account_info(),
+                            system_program: self.system_program.to_account_info(),
+                            token_program: self.collateral_token_program.to_account_info(),
+                        }
+                    ))?;
+                }
+            }
+        }
+
+        if native_loan {
+            // sol transfer debt liquidator to lender and interest fee to team_wallet
+            sol_transfer_from_user(&self.liquidator, self.lender.clone(), &self.system_program, lender_amount)?;
+            if lend_fee_amount > 0 {
+                sol_transfer_from_user(&self.liquidator, self.team_wallet.clone(), &self.system_program, lend_fee_amount)?;
+            }
+        } else {
+            // transfer debt liquidator to lender and interest fee to team_wallet
+            token_transfer_user(
+                self.liquidator_loan_ata.clone(),
+                &self.liquidator,
+                self.lender_loan_ata.clone(),
+                &self.loan_mint,
+                &self.loan_token_program,
+                lender_amount
+            )?;
+            if lend_fee_amount > 0 {
+                token_transfer_user(
+                    self.liquidator_loan_ata.clone(),
+                    &self.liquidator,
+                    self.team_loan_ata.clone(),
+                    &self.loan_mint,
+                    &self.loan_token_program,
+                    lend_fee_amount
+                )?;
+            }
+        }
+
+        let lender_key = loan.lender;
+        let loan_key = self.loan_key.key();
+        let bump = [loan_bump];
+        let signer_seeds = loan_signer_seeds(&lender_key, &loan_key, &bump);
+
+        if native_collateral {
+            let loan_info = loan.to_account_info();
+
+            // unwrap the collateral_mint pda ata into the loan pda, its rent stays there for the lender
+            token_close_with_signer(
+                loan_collateral_ata.to_account_info(),
+                loan_info.clone(),
+                loan_info.clone(),
+                &self.collateral_mint,
+                &self.collateral_token_program,
+                &[&signer_seeds[..]],
+            )?;
+
+            // sol transfer from loan to liquidator, team_wallet and borrower
+            sol_transfer_from_program_account(&loan_info, &self.liquidator.to_account_info(), liquidator_amount)?;
+            if liquidation_fee_amount > 0 {
+                sol_transfer_from_program_account(&loan_info, &self.team_wallet, liquidation_fee_amount)?;
+            }
+            if returned_amount > 0 {
+                sol_transfer_from_program_account(&loan_info, &self.borrower, returned_amount)?;
+            }
+        } else {
+            for (destination, amount) in [
+                (&self.liquidator_collateral_ata, liquidator_amount),
+                (&self.team_collateral_ata, liquidation_fee_amount),
+                (&self.borrower_collateral_ata, returned_amount),
+            ] {
+                if amount > 0 {
+                    token_transfer_with_signer(
+                        loan_collateral_ata.to_account_info(),
+                        loan.to_account_info(),
+                        destination.clone(),
+                        &self.collateral_mint,
+                        &self.collateral_token_program,
+                        &[&signer_seeds[..]],
+                        amount
+                    )?;
+                }
+            }
+
+            // close collateral vault and refund rent to whoever opened it, the borrower on a collection offer
+            let collateral_rent_to = if loan.is_collection_offer() { self.borrower.to_account_info() } else { self.lender.to_account_info() };
+            token_close_with_signer(
+                loan_collateral_ata.to_account_info(),
+                collateral_rent_to,
+                loan.to_account_info(),
+                &self.collateral_mint,
+                &self.collateral_token_program,
+                &[&signer_seeds[..]],
+            )?;
+        }
+
+        // close loan_mint vault if still open and refund rent to lender
+        token_close_if_empty_with_signer(
+            self.loan_mint_ata.clone(),
+            self.lender.to_account_info(),
+            loan.to_account_info(),
+            &self.loan_mint,
+            &self.loan_token_program,
+            &[&signer_seeds[..]],
+        )?;
+
+        Ok(LoanLiquidated {
+            loan: loan.key(),
+            lender: loan.lender,
+            borrower: loan.borrower,
+            liquidator: self.liquidator.key(),
+            loan_mint: loan.loan_mint,
+            collateral_mint: loan.collateral_mint,
+            principal,
+            interest,
+            lend_fee_amount,
+            seized_amount,
+            liquidation_fee_amount,
+            returned_amount,
+            timestamp: current_time,
+        })
+    }
+}
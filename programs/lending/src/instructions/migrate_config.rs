@@ -0,0 +1,50 @@
+use anchor_lang::{prelude::*, Discriminator};
+
+use crate::{constants::CONFIG, errors::AgioError, events::ConfigMigrated, legacy::ConfigV0, state::Config, utils::sol_transfer_from_user};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MigrateConfig<'info> {
+    /// CHECK: the old-layout global config, checked by discriminator and length
+    #[account(
+        mut,
+        seeds = [CONFIG.as_bytes()],
+        bump,
+    )]
+    pub global_config: AccountInfo<'info>,
+
+    //  must be the authority recorded in the old config, pays the extra rent
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateConfig<'info> {
+    pub fn process(&mut self) -> Result<ConfigMigrated> {
+        let config_info = self.global_config.to_account_info();
+        let space = 8 + Config::INIT_SPACE;
+
+        // old fields keep their value, new fields start neutral
+        let global_config = {
+            let data = config_info.try_borrow_data()?;
+            require!(data.len() >= 8 && data[..8] == Config::DISCRIMINATOR, AgioError::InvalidAccountLayout);
+            require!(data.len() < space, AgioError::AlreadyMigrated);
+            let legacy = ConfigV0::deserialize(&mut &data[8..]).map_err(|_| error!(AgioError::InvalidAccountLayout))?;
+            require!(legacy.authority == self.authority.key(), AgioError::IncorrectAuthority);
+            Config::from(legacy)
+        };
+
+        let top_up = Rent::get()?.minimum_balance(space).saturating_sub(config_info.lamports());
+        if top_up > 0 {
+            sol_transfer_from_user(&self.authority, config_info.clone(), &self.system_program, top_up)?;
+        }
+        config_info.realloc(space, true)?;
+        global_config.try_serialize(&mut &mut config_info.try_borrow_mut_data()?[..])?;
+
+        Ok(ConfigMigrated {
+            version: global_config.version,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
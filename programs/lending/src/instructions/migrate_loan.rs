@@ -0,0 +1,204 @@
+use anchor_lang::{prelude::*, Discriminator};
+use anchor_spl::{associated_token::AssociatedToken, token::accessor, token_interface::{Mint, TokenInterface}};
+
+use crate::{constants::LOAN, errors::AgioError, events::LoanMigrated, legacy::LoanV0, seeds::legacy_loan_signer_seeds, state::Loan, utils::{sol_transfer_from_program_account, token_close_with_signer, token_transfer_with_signer}};
+
+//  the original program kept a loan at [LOAN, loan_key] and every instruction now signs
+//  as [LOAN, lender, loan_key], so migration moves the loan and its vaults to that address
+//  and closes the old accounts
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MigrateLoan<'info> {
+    /// CHECK: a loan written by the original program, checked by discriminator and length
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [LOAN.as_bytes(), &loan_key.key().to_bytes()],
+        bump,
+    )]
+    pub legacy_loan: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Loan::INIT_SPACE,
+        seeds = [LOAN.as_bytes(), &lender.key().to_bytes(), &loan_key.key().to_bytes()],
+        bump,
+    )]
+    pub loan: Box<Account<'info, Loan>>,
+
+    #[account(mint::token_program = token_program)]
+    pub loan_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mint::token_program = token_program)]
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: the old loan's ata, skipped when it is already closed
+    #[account(
+        mut,
+        seeds = [
+            legacy_loan.key().as_ref(),
+            token_program.key().as_ref(),
+            loan_mint.key().as_ref(),
+        ],
+        seeds::program = anchor_spl::associated_token::ID,
+        bump,
+    )]
+    pub legacy_loan_mint_ata: AccountInfo<'info>,
+
+    /// CHECK: the old loan's ata, skipped when it is already closed
+    #[account(
+        mut,
+        seeds = [
+            legacy_loan.key().as_ref(),
+            token_program.key().as_ref(),
+            collateral_mint.key().as_ref(),
+        ],
+        seeds::program = anchor_spl::associated_token::ID,
+        bump,
+    )]
+    pub legacy_collateral_ata: AccountInfo<'info>,
+
+    /// CHECK: created here when the old vault still exists
+    #[account(
+        mut,
+        seeds = [
+            loan.key().as_ref(),
+            token_program.key().as_ref(),
+            loan_mint.key().as_ref(),
+        ],
+        seeds::program = anchor_spl::associated_token::ID,
+        bump,
+    )]
+    pub loan_mint_ata: AccountInfo<'info>,
+
+    /// CHECK: created here when the old vault still exists
+    #[account(
+        mut,
+        seeds = [
+            loan.key().as_ref(),
+            token_program.key().as_ref(),
+            collateral_mint.key().as_ref(),
+        ],
+        seeds::program = anchor_spl::associated_token::ID,
+        bump,
+    )]
+    pub loan_collateral_ata: AccountInfo<'info>,
+
+    /// CHECK: checked against the old loan, gets back the rent of the closed accounts it paid for
+    #[account(mut)]
+    pub lender: AccountInfo<'info>,
+
+    pub loan_key: Box<InterfaceAccount<'info, Mint>>,
+
+    //  anyone may migrate, the payer covers the rent of the new accounts
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    //  the original program only knew spl-token
+    #[account(address = anchor_spl::token::ID)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateLoan<'info> {
+    pub fn process(&mut self, legacy_bump: u8) -> Result<LoanMigrated> {
+        let legacy_info = self.legacy_loan.to_account_info();
+
+        // old fields keep their value, the status comes from the old repaid flag and new fields start neutral
+        let legacy = {
+            let data = legacy_info.try_borrow_data()?;
+            require!(data.len() >= 8 && data[..8] == Loan::DISCRIMINATOR, AgioError::InvalidAccountLayout);
+            require!(data.len() < 8 + Loan::INIT_SPACE, AgioError::AlreadyMigrated);
+            LoanV0::deserialize(&mut &data[8..]).map_err(|_| error!(AgioError::InvalidAccountLayout))?
+        };
+        require!(legacy.lender == self.lender.key(), AgioError::IncorrectAuthority);
+        require!(legacy.loan_mint == self.loan_mint.key(), AgioError::InvalidLoanMint);
+        require!(legacy.collateral_mint == self.collateral_mint.key(), AgioError::InvalidCollateral);
+
+        self.loan.set_inner(Loan::from(legacy));
+
+        let loan_key = self.loan_key.key();
+        let bump = [legacy_bump];
+        let signer_seeds = legacy_loan_signer_seeds(&loan_key, &bump);
+
+        self.move_vault(
+            self.legacy_loan_mint_ata.clone(),
+            self.loan_mint_ata.clone(),
+            &self.loan_mint,
+            &[&signer_seeds[..]],
+        )?;
+        // both mints share one vault when the offer lent and took the same token
+        if self.collateral_mint.key() != self.loan_mint.key() {
+            self.move_vault(
+                self.legacy_collateral_ata.clone(),
+                self.loan_collateral_ata.clone(),
+                &self.collateral_mint,
+                &[&signer_seeds[..]],
+            )?;
+        }
+
+        // close the old loan, its rent goes back to the lender who paid for it
+        sol_transfer_from_program_account(&legacy_info, &self.lender, legacy_info.lamports())?;
+        legacy_info.realloc(0, false)?;
+        legacy_info.assign(&System::id());
+
+        Ok(LoanMigrated {
+            legacy_loan: legacy_info.key(),
+            loan: self.loan.key(),
+            version: self.loan.version,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+
+    //  moves a whole old vault into the loan's vault at the new address and closes it
+    fn move_vault(
+        &self,
+        legacy_vault: AccountInfo<'info>,
+        vault: AccountInfo<'info>,
+        mint: &InterfaceAccount<'info, Mint>,
+        signer_seeds: &[&[&[u8]]; 1],
+    ) -> Result<()> {
+        if legacy_vault.data_is_empty() {
+            return Ok(());
+        }
+
+        if vault.data_is_empty() {
+            anchor_spl::associated_token::create(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                anchor_spl::associated_token::Create {
+                    payer: self.payer.to_account_info(),
+                    associated_token: vault.clone(),
+                    authority: self.loan.to_account_info(),
+
+                    mint: mint.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: self.token_program.to_account_info(),
+                }
+            ))?;
+        }
+
+        let amount = accessor::amount(&legacy_vault)?;
+        if amount > 0 {
+            token_transfer_with_signer(
+                legacy_vault.clone(),
+                self.legacy_loan.to_account_info(),
+                vault,
+                mint,
+                &self.token_program,
+                signer_seeds,
+                amount
+            )?;
+        }
+
+        token_close_with_signer(
+            legacy_vault,
+            self.lender.to_account_info(),
+            self.legacy_loan.to_account_info(),
+            mint,
+            &self.token_program,
+            signer_seeds,
+        )
+    }
+}
@@ -1,11 +1,53 @@
-pub mod configure;
-pub use configure::*;
+pub mod initialize_config;
+pub use initialize_config::*;
+pub mod set_fees;
+pub use set_fees::*;
+pub mod set_max_fee;
+pub use set_max_fee::*;
+pub mod set_team_wallet;
+pub use set_team_wallet::*;
+pub mod set_expire_duration;
+pub use set_expire_duration::*;
+pub mod set_liquidation_params;
+pub use set_liquidation_params::*;
+pub mod set_guardian;
+pub use set_guardian::*;
+pub mod set_pause;
+pub use set_pause::*;
+pub mod set_asset_config;
+pub use set_asset_config::*;
+pub mod migrate_config;
+pub use migrate_config::*;
 pub mod create_loan;
 pub use create_loan::*;
+pub mod update_offer;
+pub use update_offer::*;
 pub mod borrow_loan;
 pub use borrow_loan::*;
 pub mod cancel_loan;
 pub use cancel_loan::*;
 pub mod repay_loan;
 pub use repay_loan::*;
+pub mod repay_partial;
+pub use repay_partial::*;
+pub mod extend_loan;
+pub use extend_loan::*;
 
+pub mod claim_collateral;
+pub use claim_collateral::*;
+pub mod liquidate;
+pub use liquidate::*;
+pub mod create_request;
+pub use create_request::*;
+pub mod fill_request;
+pub use fill_request::*;
+pub mod cancel_request;
+pub use cancel_request::*;
+pub mod migrate_loan;
+pub use migrate_loan::*;
+pub mod nominate_authority;
+pub use nominate_authority::*;
+pub mod accept_authority;
+pub use accept_authority::*;
+pub mod cancel_authority_nomination;
+pub use cancel_authority_nomination::*;
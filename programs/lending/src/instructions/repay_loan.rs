@@ -1,10 +1,11 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{associated_token::AssociatedToken, token::{Mint, Token}};
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface}};
 
-use crate::{constants::{CONFIG, LOAN}, errors::AgioError, state::{Config, Loan}, utils::{token_transfer_user, token_transfer_with_signer}};
+use crate::{constants::{BORROWER_PAGE, CONFIG, LENDER_PAGE, LOAN, PROFILE}, errors::AgioError, events::LoanRepaid, fees, portfolio, seeds::loan_signer_seeds, state::{Config, Loan, LoanPage, LoanStatus, PortfolioRole, UserProfile}, utils::{is_native_mint, sol_transfer_from_program_account, sol_transfer_from_user, token_close_if_empty_with_signer, token_close_with_signer, token_transfer_user, token_transfer_with_signer}};
 
 
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct RepayLoan<'info> {
 
@@ -17,34 +18,55 @@ pub struct RepayLoan<'info> {
 
     #[account(
         mut,
-        seeds = [LOAN.as_bytes(), &loan_key.key().to_bytes()],
+        close = lender,
+        seeds = [LOAN.as_bytes(), &lender.key().to_bytes(), &loan_key.key().to_bytes()],
         bump,
     )]
     loan: Account<'info, Loan>,
 
-    #[account(mut)]
-    collateral_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        constraint = collateral_mint.key() == loan.collateral_mint @AgioError::InvalidCollateral,
+        mint::token_program = collateral_token_program,
+    )]
+    collateral_mint: InterfaceAccount<'info, Mint>,
 
-    #[account(mut)]
-    loan_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        constraint = loan_mint.key() == loan.loan_mint @AgioError::InvalidLoanMint,
+        mint::token_program = loan_token_program,
+    )]
+    loan_mint: InterfaceAccount<'info, Mint>,
 
     #[account(
         mut,
         seeds = [
             loan.key().as_ref(),
-            anchor_spl::token::spl_token::ID.as_ref(),
+            collateral_token_program.key().as_ref(),
             collateral_mint.key().as_ref(),
         ],
         seeds::program = anchor_spl::associated_token::ID,
         bump,
     )]
-    loan_collateral_ata: AccountInfo<'info>,
+    loan_collateral_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [
+            loan.key().as_ref(),
+            loan_token_program.key().as_ref(),
+            loan_mint.key().as_ref(),
+        ],
+        seeds::program = anchor_spl::associated_token::ID,
+        bump,
+    )]
+    loan_mint_ata: AccountInfo<'info>,
 
     #[account(
         mut,
         seeds = [
             borrower.key().as_ref(),
-            anchor_spl::token::spl_token::ID.as_ref(),
+            collateral_token_program.key().as_ref(),
             collateral_mint.key().as_ref(),
         ],
         seeds::program = anchor_spl::associated_token::ID,
@@ -56,7 +78,7 @@ pub struct RepayLoan<'info> {
         mut,
         seeds = [
             lender.key().as_ref(),
-            anchor_spl::token::spl_token::ID.as_ref(),
+            loan_token_program.key().as_ref(),
             loan_mint.key().as_ref(),
         ],
         bump,
@@ -68,7 +90,7 @@ pub struct RepayLoan<'info> {
         mut,
         seeds = [
             borrower.key().as_ref(),
-            anchor_spl::token::spl_token::ID.as_ref(),
+            loan_token_program.key().as_ref(),
             loan_mint.key().as_ref(),
         ],
         bump,
@@ -80,7 +102,7 @@ pub struct RepayLoan<'info> {
         mut,
         seeds = [
             team_wallet.key().as_ref(),
-            anchor_spl::token::spl_token::ID.as_ref(),
+            loan_token_program.key().as_ref(),
             loan_mint.key().as_ref(),
         ],
         bump,
@@ -94,14 +116,50 @@ pub struct RepayLoan<'info> {
     )]
     pub team_wallet: AccountInfo<'info>,
 
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = borrower.key() == loan.borrower @AgioError::IncorrectAuthority,
+    )]
     pub borrower: Signer<'info>,
 
-    pub loan_key: Account<'info, Mint>,
+    pub loan_key: InterfaceAccount<'info, Mint>,
+
+    //  receives the rent of the loan and its vaults, except a collection offer's collateral vault
+    #[account(mut)]
     pub lender: AccountInfo<'info>,
 
+    //  portfolio index, only required for loans listed when they were created
+    #[account(
+        mut,
+        seeds = [PROFILE.as_bytes(), lender.key().as_ref()],
+        bump,
+    )]
+    lender_profile: Option<Box<Account<'info, UserProfile>>>,
+
+    #[account(
+        mut,
+        seeds = [LENDER_PAGE.as_bytes(), lender.key().as_ref(), &loan.lender_page_index().to_le_bytes()],
+        bump,
+    )]
+    lender_page: Option<Box<Account<'info, LoanPage>>>,
+
+    #[account(
+        mut,
+        seeds = [PROFILE.as_bytes(), borrower.key().as_ref()],
+        bump,
+    )]
+    borrower_profile: Option<Box<Account<'info, UserProfile>>>,
+
+    #[account(
+        mut,
+        seeds = [BORROWER_PAGE.as_bytes(), borrower.key().as_ref(), &loan.borrower_page_index().to_le_bytes()],
+        bump,
+    )]
+    borrower_page: Option<Box<Account<'info, LoanPage>>>,
+
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    pub loan_token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub rent: Sysvar<'info, Rent>,
 
@@ -111,22 +169,44 @@ impl<'info> RepayLoan<'info> {
     pub fn process(
         &mut self,
         loan_bump: u8
-    ) -> Result<()> {
+    ) -> Result<LoanRepaid> {
+        let clock = Clock::get()?;
+        let current_time = clock.unix_timestamp;
 
         let global_config = &mut self.global_config;
 
         let loan = &mut self.loan;
-        let loan_collateral_ata = &mut self.loan_collateral_ata;
+        let loan_collateral_ata = &self.loan_collateral_ata;
+        let loan_mint_ata = &self.loan_mint_ata;
         let borrower_collateral_ata = &mut self.borrower_collateral_ata;
         let lender_loan_ata = &mut self.lender_loan_ata;
         let borrower_loan_ata = &mut self.borrower_loan_ata;
         let team_loan_ata = &mut self.team_loan_ata;
 
-        require!(loan.repaid == false, AgioError::AlreadyRepaid);
-        loan.repaid = true;
+        loan.transition(LoanStatus::Repaid)?;
+
+        portfolio::unlist(
+            PortfolioRole::Lender,
+            loan.key(),
+            loan.lender_slot,
+            self.lender_profile.as_mut(),
+            self.lender_page.as_mut(),
+            &self.lender.to_account_info(),
+        )?;
+        portfolio::unlist(
+            PortfolioRole::Borrower,
+            loan.key(),
+            loan.borrower_slot,
+            self.borrower_profile.as_mut(),
+            self.borrower_page.as_mut(),
+            &self.borrower.to_account_info(),
+        )?;
+
+        let native_loan = is_native_mint(&loan.loan_mint);
+        let native_collateral = is_native_mint(&loan.collateral_mint);
 
         // create borrower collateral_mint ata if it doesn't exit
-        if borrower_collateral_ata.data_is_empty() {
+        if !native_collateral && borrower_collateral_ata.data_is_empty() {
             anchor_spl::associated_token::create(CpiContext::new(
                 self.associated_token_program.to_account_info(),
                 anchor_spl::associated_token::Create {
@@ -136,75 +216,153 @@ impl<'info> RepayLoan<'info> {
 
                     mint: self.collateral_mint.to_account_info(),
                     system_program: self.system_program.to_account_info(),
-                    token_program: self.token_program.to_account_info(),
+                    token_program: self.collateral_token_program.to_account_info(),
                 }
             ))?;
         }
 
-        // create team_wallet loan_mint ata if it doesn't exit
-        if lender_loan_ata.data_is_empty() {
+        // create lender loan_mint ata if it doesn't exit
+        if !native_loan && lender_loan_ata.data_is_empty() {
             anchor_spl::associated_token::create(CpiContext::new(
                 self.associated_token_program.to_account_info(),
                 anchor_spl::associated_token::Create {
                     payer: self.borrower.to_account_info(),
                     associated_token: lender_loan_ata.to_account_info(),
-                    authority: self.borrower.to_account_info(),
+                    authority: self.lender.to_account_info(),
 
                     mint: self.loan_mint.to_account_info(),
                     system_program: self.system_program.to_account_info(),
-                    token_program: self.token_program.to_account_info(),
+                    token_program: self.loan_token_program.to_account_info(),
                 }
             ))?;
         }
 
-        // create lender loan_mint ata if it doesn't exit
-        if team_loan_ata.data_is_empty() {
+        // create team_wallet loan_mint ata if it doesn't exit
+        if !native_loan && team_loan_ata.data_is_empty() {
             anchor_spl::associated_token::create(CpiContext::new(
                 self.associated_token_program.to_account_info(),
                 anchor_spl::associated_token::Create {
                     payer: self.borrower.to_account_info(),
                     associated_token: team_loan_ata.to_account_info(),
-                    authority: self.borrower.to_account_info(),
+                    authority: self.team_wallet.to_account_info(),
 
                     mint: self.loan_mint.to_account_info(),
                     system_program: self.system_program.to_account_info(),
-                    token_program: self.token_program.to_account_info(),
+                    token_program: self.loan_token_program.to_account_info(),
                 }
             ))?;
         }
 
-        let fee_amount = (loan.loan_amount / 100)
-            .checked_mul(global_config.lend_fee)
+        // interest accrued since the last payment, capped at maturity
+        let principal = loan.principal_outstanding;
+        let interest = loan.interest_owed(current_time)?;
+
+        // team's cut is taken out of the interest, never the principal
+        let (lender_interest, fee_amount) = fees::split(interest, fees::lend_fee(interest, global_config)?)?;
+
+        let lender_amount = principal
+            .checked_add(lender_interest)
             .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
 
-        // trnasfer collateral_mint pda to borrower
-        token_transfer_with_signer(
-            loan_collateral_ata.clone(), 
-            loan.to_account_info(), 
-            borrower_collateral_ata.clone(), 
-            &self.token_program, 
-            &[&[LOAN.as_bytes(), loan.key().as_ref(), &[loan_bump]]], 
-            loan.collateral_amount
-        )?;
+        let lender_key = loan.lender;
+        let loan_key = self.loan_key.key();
+        let bump = [loan_bump];
+        let signer_seeds = loan_signer_seeds(&lender_key, &loan_key, &bump);
+
+        let collateral_amount = loan_collateral_ata.amount;
+
+        if native_collateral {
+            let loan_info = loan.to_account_info();
+
+            // unwrap the collateral_mint pda ata into the loan pda, its rent stays there for the lender
+            token_close_with_signer(
+                loan_collateral_ata.to_account_info(),
+                loan_info.clone(),
+                loan_info.clone(),
+                &self.collateral_mint,
+                &self.collateral_token_program,
+                &[&signer_seeds[..]],
+            )?;
+
+            // sol transfer from loan to borrower
+            sol_transfer_from_program_account(&loan_info, &self.borrower.to_account_info(), collateral_amount)?;
+        } else {
+            // trnasfer collateral_mint pda to borrower
+            token_transfer_with_signer(
+                loan_collateral_ata.to_account_info(), 
+                loan.to_account_info(), 
+                borrower_collateral_ata.clone(), 
+                &self.collateral_mint,
+                &self.collateral_token_program, 
+                &[&signer_seeds[..]], 
+                collateral_amount
+            )?;
+
+            // close collateral vault and refund rent to whoever opened it, the borrower on a collection offer
+            let collateral_rent_to = if loan.is_collection_offer() { self.borrower.to_account_info() } else { self.lender.to_account_info() };
+            token_close_with_signer(
+                loan_collateral_ata.to_account_info(),
+                collateral_rent_to,
+                loan.to_account_info(),
+                &self.collateral_mint,
+                &self.collateral_token_program,
+                &[&signer_seeds[..]],
+            )?;
+        }
 
-        // transfer loan_mint borrower to lender
-        token_transfer_user(
-            borrower_loan_ata.clone(), 
-            &self.borrower, 
-            lender_loan_ata.clone(), 
-            &self.token_program, 
-            loan.loan_amount - fee_amount
-        )?;
+        if native_loan {
+            // sol transfer principal and interest borrower to lender
+            sol_transfer_from_user(&self.borrower, self.lender.clone(), &self.system_program, lender_amount)?;
+
+            // sol transfer interest fee borrower to team_wallet
+            if fee_amount > 0 {
+                sol_transfer_from_user(&self.borrower, self.team_wallet.clone(), &self.system_program, fee_amount)?;
+            }
+        } else {
+            // transfer principal and interest borrower to lender
+            token_transfer_user(
+                borrower_loan_ata.clone(), 
+                &self.borrower, 
+                lender_loan_ata.clone(), 
+                &self.loan_mint,
+                &self.loan_token_program, 
+                lender_amount
+            )?;
+
+            // transfer interest fee borrower to team_wallet
+            if fee_amount > 0 {
+                token_transfer_user(
+                    borrower_loan_ata.clone(), 
+                    &self.borrower, 
+                    team_loan_ata.clone(),
+                    &self.loan_mint,
+                    &self.loan_token_program, 
+                    fee_amount
+                )?;
+            }
+        }
 
-        // transfer loan_mint borrower to team_wallet
-        token_transfer_user(
-            borrower_loan_ata.clone(), 
-            &self.borrower, 
-            team_loan_ata.clone(),
-            &self.token_program, 
-            fee_amount * 2
+        // close loan_mint vault if still open and refund rent to lender
+        token_close_if_empty_with_signer(
+            loan_mint_ata.clone(),
+            self.lender.to_account_info(),
+            loan.to_account_info(),
+            &self.loan_mint,
+            &self.loan_token_program,
+            &[&signer_seeds[..]],
         )?;
         
-        Ok(())
+        Ok(LoanRepaid {
+            loan: loan.key(),
+            lender: loan.lender,
+            borrower: loan.borrower,
+            loan_mint: loan.loan_mint,
+            collateral_mint: loan.collateral_mint,
+            principal,
+            interest,
+            fee_amount,
+            collateral_amount,
+            timestamp: current_time,
+        })
     }
 }
\ No newline at end of file
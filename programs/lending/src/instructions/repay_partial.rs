@@ -0,0 +1,464 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface}};
+
+use crate::{constants::{BORROWER_PAGE, CONFIG, LENDER_PAGE, LOAN, PROFILE}, errors::AgioError, events::LoanPartiallyRepaid, fees, portfolio, seeds::loan_signer_seeds, state::{Config, Loan, LoanPage, LoanStatus, PortfolioRole, UserProfile}, utils::{is_native_mint, sol_transfer_from_program_account, sol_transfer_from_user, token_close_if_empty_with_signer, token_close_with_signer, token_transfer_user, token_transfer_with_signer, wrap_sol_from_program_account}};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RepayPartial<'info> {
+
+    #[account(
+        mut,
+        seeds = [CONFIG.as_bytes()],
+        bump,
+    )]
+    global_config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [LOAN.as_bytes(), &lender.key().to_bytes(), &loan_key.key().to_bytes()],
+        bump,
+    )]
+    loan: Account<'info, Loan>,
+
+    #[account(
+        mut,
+        constraint = collateral_mint.key() == loan.collateral_mint @AgioError::InvalidCollateral,
+        mint::token_program = collateral_token_program,
+    )]
+    collateral_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = loan_mint.key() == loan.loan_mint @AgioError::InvalidLoanMint,
+        mint::token_program = loan_token_program,
+    )]
+    loan_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [
+            loan.key().as_ref(),
+            collateral_token_program.key().as_ref(),
+            collateral_mint.key().as_ref(),
+        ],
+        seeds::program = anchor_spl::associated_token::ID,
+        bump,
+    )]
+    loan_collateral_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [
+            loan.key().as_ref(),
+            loan_token_program.key().as_ref(),
+            loan_mint.key().as_ref(),
+        ],
+        seeds::program = anchor_spl::associated_token::ID,
+        bump,
+    )]
+    loan_mint_ata: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            borrower.key().as_ref(),
+            collateral_token_program.key().as_ref(),
+            collateral_mint.key().as_ref(),
+        ],
+        seeds::program = anchor_spl::associated_token::ID,
+        bump,
+    )]
+    borrower_collateral_ata: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            lender.key().as_ref(),
+            loan_token_program.key().as_ref(),
+            loan_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::associated_token::ID
+    )]
+    lender_loan_ata: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            borrower.key().as_ref(),
+            loan_token_program.key().as_ref(),
+            loan_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::associated_token::ID
+    )]
+    borrower_loan_ata: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            team_wallet.key().as_ref(),
+            loan_token_program.key().as_ref(),
+            loan_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::associated_token::ID
+    )]
+    team_loan_ata: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = team_wallet.key() == global_config.team_wallet @AgioError::IncorrectAuthority
+    )]
+    pub team_wallet: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = borrower.key() == loan.borrower @AgioError::IncorrectAuthority,
+    )]
+    pub borrower: Signer<'info>,
+
+    pub loan_key: InterfaceAccount<'info, Mint>,
+
+    //  receives the payments, and the rent of the loan and its vaults once fully repaid,
+    //  except a collection offer's collateral vault
+    #[account(mut)]
+    pub lender: AccountInfo<'info>,
+
+    //  portfolio index, only required for loans listed when they were created
+    #[account(
+        mut,
+        seeds = [PROFILE.as_bytes(), lender.key().as_ref()],
+        bump,
+    )]
+    lender_profile: Option<Box<Account<'info, UserProfile>>>,
+
+    #[account(
+        mut,
+        seeds = [LENDER_PAGE.as_bytes(), lender.key().as_ref(), &loan.lender_page_index().to_le_bytes()],
+        bump,
+    )]
+    lender_page: Option<Box<Account<'info, LoanPage>>>,
+
+    #[account(
+        mut,
+        seeds = [PROFILE.as_bytes(), borrower.key().as_ref()],
+        bump,
+    )]
+    borrower_profile: Option<Box<Account<'info, UserProfile>>>,
+
+    #[account(
+        mut,
+        seeds = [BORROWER_PAGE.as_bytes(), borrower.key().as_ref(), &loan.borrower_page_index().to_le_bytes()],
+        bump,
+    )]
+    borrower_page: Option<Box<Account<'info, LoanPage>>>,
+
+    pub system_program: Program<'info, System>,
+    pub loan_token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>,
+
+}
+
+impl<'info> RepayPartial<'info> {
+    pub fn process(
+        &mut self,
+        amount: u64,
+        loan_bump: u8
+    ) -> Result<LoanPartiallyRepaid> {
+        let clock = Clock::get()?;
+        let current_time = clock.unix_timestamp;
+
+        let global_config = &self.global_config;
+
+        let loan = &mut self.loan;
+        let loan_collateral_ata = &self.loan_collateral_ata;
+        let borrower_collateral_ata = &self.borrower_collateral_ata;
+        let lender_loan_ata = &self.lender_loan_ata;
+        let borrower_loan_ata = &self.borrower_loan_ata;
+        let team_loan_ata = &self.team_loan_ata;
+
+        loan.status.require_active()?;
+        require!(amount > 0, AgioError::InvalidRepayAmount);
+
+        let native_loan = is_native_mint(&loan.loan_mint);
+        let native_collateral = is_native_mint(&loan.collateral_mint);
+
+        // create borrower collateral_mint ata if it doesn't exit
+        if !native_collateral && borrower_collateral_ata.data_is_empty() {
+            anchor_spl::associated_token::create(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                anchor_spl::associated_token::Create {
+                    payer: self.borrower.to_account_info(),
+                    associated_token: borrower_collateral_ata.to_account_info(),
+                    authority: self.borrower.to_account_info(),
+
+                    mint: self.collateral_mint.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: self.collateral_token_program.to_account_info(),
+                }
+            ))?;
+        }
+
+        // create lender loan_mint ata if it doesn't exit
+        if !native_loan && lender_loan_ata.data_is_empty() {
+            anchor_spl::associated_token::create(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                anchor_spl::associated_token::Create {
+                    payer: self.borrower.to_account_info(),
+                    associated_token: lender_loan_ata.to_account_info(),
+                    authority: self.lender.to_account_info(),
+
+                    mint: self.loan_mint.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: self.loan_token_program.to_account_info(),
+                }
+            ))?;
+        }
+
+        // create team_wallet loan_mint ata if it doesn't exit
+        if !native_loan && team_loan_ata.data_is_empty() {
+            anchor_spl::associated_token::create(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                anchor_spl::associated_token::Create {
+                    payer: self.borrower.to_account_info(),
+                    associated_token: team_loan_ata.to_account_info(),
+                    authority: self.team_wallet.to_account_info(),
+
+                    mint: self.loan_mint.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: self.loan_token_program.to_account_info(),
+                }
+            ))?;
+        }
+
+        // the payment settles accrued interest first, then principal, and never more than is owed
+        let elapsed = loan.accrual_seconds(current_time)?;
+        let interest_owed = loan.interest_owed(current_time)?;
+        let interest = amount.min(interest_owed);
+        let principal = amount
+            .checked_sub(interest)
+            .ok_or(AgioError::OverflowOrUnderflowOccurred)?
+            .min(loan.principal_outstanding);
+
+        // team's cut is taken out of the interest, never the principal
+        let (lender_interest, fee_amount) = fees::split(interest, fees::lend_fee(interest, global_config)?)?;
+
+        let lender_amount = principal
+            .checked_add(lender_interest)
+            .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+
+        // interest paid in full moves the accrual baseline to now, a part payment
+        // only moves it over the seconds it covers, rounded in the lender's favour
+        let covered = if interest == interest_owed {
+            elapsed
+        } else {
+            ((elapsed as u128)
+                .checked_mul(interest as u128)
+                .ok_or(AgioError::OverflowOrUnderflowOccurred)?
+                / interest_owed as u128) as i64
+        };
+        loan.last_payment_ts = loan.last_payment_ts
+            .checked_add(covered)
+            .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+
+        // collateral is released in proportion to the principal paid down
+        let collateral_balance = loan_collateral_ata.amount;
+        let collateral_released = if principal == loan.principal_outstanding {
+            collateral_balance
+        } else {
+            ((collateral_balance as u128)
+                .checked_mul(principal as u128)
+                .ok_or(AgioError::OverflowOrUnderflowOccurred)?
+                / loan.principal_outstanding as u128) as u64
+        };
+
+        loan.principal_outstanding = loan.principal_outstanding
+            .checked_sub(principal)
+            .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+        loan.interest_paid = loan.interest_paid
+            .checked_add(interest)
+            .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+        loan.collateral_amount = collateral_balance
+            .checked_sub(collateral_released)
+            .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+
+        let fully_repaid = loan.principal_outstanding == 0;
+        if fully_repaid {
+            loan.transition(LoanStatus::Repaid)?;
+
+            portfolio::unlist(
+                PortfolioRole::Lender,
+                loan.key(),
+                loan.lender_slot,
+                self.lender_profile.as_mut(),
+                self.lender_page.as_mut(),
+                &self.lender.to_account_info(),
+            )?;
+            portfolio::unlist(
+                PortfolioRole::Borrower,
+                loan.key(),
+                loan.borrower_slot,
+                self.borrower_profile.as_mut(),
+                self.borrower_page.as_mut(),
+                &self.borrower.to_account_info(),
+            )?;
+        }
+
+        if native_loan {
+            // sol transfer principal and interest borrower to lender
+            sol_transfer_from_user(&self.borrower, self.lender.clone(), &self.system_program, lender_amount)?;
+
+            // sol transfer interest fee borrower to team_wallet
+            if fee_amount > 0 {
+                sol_transfer_from_user(&self.borrower, self.team_wallet.clone(), &self.system_program, fee_amount)?;
+            }
+        } else {
+            // transfer principal and interest borrower to lender
+            token_transfer_user(
+                borrower_loan_ata.clone(),
+                &self.borrower,
+                lender_loan_ata.clone(),
+                &self.loan_mint,
+                &self.loan_token_program,
+                lender_amount
+            )?;
+
+            // transfer interest fee borrower to team_wallet
+            if fee_amount > 0 {
+                token_transfer_user(
+                    borrower_loan_ata.clone(),
+                    &self.borrower,
+                    team_loan_ata.clone(),
+                    &self.loan_mint,
+                    &self.loan_token_program,
+                    fee_amount
+                )?;
+            }
+        }
+
+        let lender_key = loan.lender;
+        let loan_key = self.loan_key.key();
+        let bump = [loan_bump];
+        let signer_seeds = loan_signer_seeds(&lender_key, &loan_key, &bump);
+
+        if native_collateral {
+            if fully_repaid {
+                let loan_info = loan.to_account_info();
+
+                // unwrap the collateral_mint pda ata into the loan pda, its rent stays there for the lender
+                token_close_with_signer(
+                    loan_collateral_ata.to_account_info(),
+                    loan_info.clone(),
+                    loan_info.clone(),
+                    &self.collateral_mint,
+                    &self.collateral_token_program,
+                    &[&signer_seeds[..]],
+                )?;
+
+                // sol transfer from loan to borrower
+                sol_transfer_from_program_account(&loan_info, &self.borrower.to_account_info(), collateral_released)?;
+            } else if collateral_released > 0 {
+                let loan_info = loan.to_account_info();
+                let vault_rent = loan_collateral_ata.to_account_info().lamports()
+                    .checked_sub(collateral_balance)
+                    .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+
+                // unwrap the whole vault into the loan pda, pay the released sol out and re-wrap the rest
+                token_close_with_signer(
+                    loan_collateral_ata.to_account_info(),
+                    loan_info.clone(),
+                    loan_info.clone(),
+                    &self.collateral_mint,
+                    &self.collateral_token_program,
+                    &[&signer_seeds[..]],
+                )?;
+                sol_transfer_from_program_account(&loan_info, &self.borrower.to_account_info(), collateral_released)?;
+
+                anchor_spl::associated_token::create(CpiContext::new(
+                    self.associated_token_program.to_account_info(),
+                    anchor_spl::associated_token::Create {
+                        payer: self.borrower.to_account_info(),
+                        associated_token: loan_collateral_ata.to_account_info(),
+                        authority: loan_info.clone(),
+
+                        mint: self.collateral_mint.to_account_info(),
+                        system_program: self.system_program.to_account_info(),
+                        token_program: self.collateral_token_program.to_account_info(),
+                    }
+                ))?;
+                // the new vault's rent is the lender's old vault rent, handed back to the borrower who fronted it
+                sol_transfer_from_program_account(&loan_info, &self.borrower.to_account_info(), vault_rent)?;
+                wrap_sol_from_program_account(
+                    &loan_info,
+                    loan_collateral_ata.to_account_info(),
+                    &self.collateral_token_program,
+                    loan.collateral_amount
+                )?;
+            }
+        } else {
+            if collateral_released > 0 {
+                // transfer released collateral_mint pda to borrower
+                token_transfer_with_signer(
+                    loan_collateral_ata.to_account_info(),
+                    loan.to_account_info(),
+                    borrower_collateral_ata.clone(),
+                    &self.collateral_mint,
+                    &self.collateral_token_program,
+                    &[&signer_seeds[..]],
+                    collateral_released
+                )?;
+            }
+
+            // close collateral vault and refund rent to whoever opened it, the borrower on a collection offer
+            if fully_repaid {
+                let collateral_rent_to = if loan.is_collection_offer() { self.borrower.to_account_info() } else { self.lender.to_account_info() };
+                token_close_with_signer(
+                    loan_collateral_ata.to_account_info(),
+                    collateral_rent_to,
+                    loan.to_account_info(),
+                    &self.collateral_mint,
+                    &self.collateral_token_program,
+                    &[&signer_seeds[..]],
+                )?;
+            }
+        }
+
+        if fully_repaid {
+            // close loan_mint vault if still open and refund rent to lender
+            token_close_if_empty_with_signer(
+                self.loan_mint_ata.clone(),
+                self.lender.to_account_info(),
+                loan.to_account_info(),
+                &self.loan_mint,
+                &self.loan_token_program,
+                &[&signer_seeds[..]],
+            )?;
+        }
+
+        let event = LoanPartiallyRepaid {
+            loan: loan.key(),
+            lender: loan.lender,
+            borrower: loan.borrower,
+            loan_mint: loan.loan_mint,
+            collateral_mint: loan.collateral_mint,
+            principal,
+            interest,
+            fee_amount,
+            collateral_released,
+            principal_outstanding: loan.principal_outstanding,
+            fully_repaid,
+            timestamp: current_time,
+        };
+
+        // the last payment closes the loan and refunds its rent to the lender
+        if fully_repaid {
+            loan.close(self.lender.to_account_info())?;
+        }
+
+        Ok(event)
+    }
+}
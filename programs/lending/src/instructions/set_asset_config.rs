@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{constants::{ASSET, CONFIG, PAUSE_FEES}, errors::AgioError, events::AssetConfigUpdated, state::{AssetConfig, AssetParams, Config}};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetAssetConfig<'info> {
+    #[account(
+        seeds = [CONFIG.as_bytes()],
+        bump,
+        constraint = global_config.authority == authority.key() @AgioError::IncorrectAuthority
+    )]
+    global_config: Box<Account<'info, Config>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + AssetConfig::INIT_SPACE,
+        seeds = [ASSET.as_bytes(), mint.key().as_ref()],
+        bump,
+    )]
+    asset_config: Box<Account<'info, AssetConfig>>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetAssetConfig<'info> {
+    pub fn process(&mut self, params: AssetParams) -> Result<AssetConfigUpdated> {
+        let asset_config = &mut self.asset_config;
+
+        //  while fees are paused the override is frozen, the rest of the record may still change
+        require!(
+            !self.global_config.is_paused(PAUSE_FEES) || params.borrow_fee == asset_config.borrow_fee,
+            AgioError::Paused
+        );
+
+        asset_config.mint = self.mint.key();
+        asset_config.allowed_as_loan = params.allowed_as_loan;
+        asset_config.allowed_as_collateral = params.allowed_as_collateral;
+        asset_config.min_loan_amount = params.min_loan_amount;
+        asset_config.max_loan_amount = params.max_loan_amount;
+        asset_config.borrow_fee = params.borrow_fee;
+        asset_config.price_feed = params.price_feed;
+        asset_config.validate(&self.global_config)?;
+
+        Ok(AssetConfigUpdated {
+            mint: asset_config.mint,
+            allowed_as_loan: asset_config.allowed_as_loan,
+            allowed_as_collateral: asset_config.allowed_as_collateral,
+            min_loan_amount: asset_config.min_loan_amount,
+            max_loan_amount: asset_config.max_loan_amount,
+            borrow_fee: asset_config.borrow_fee,
+            price_feed: asset_config.price_feed,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
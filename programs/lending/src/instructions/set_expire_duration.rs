@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::CONFIG, errors::AgioError, events::ExpireDurationUpdated, state::Config};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetExpireDuration<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG.as_bytes()],
+        bump,
+        constraint = global_config.authority == authority.key() @AgioError::IncorrectAuthority
+    )]
+    global_config: Box<Account<'info, Config>>,
+
+    pub authority: Signer<'info>,
+}
+
+impl<'info> SetExpireDuration<'info> {
+    //  expire_duration is in days
+    pub fn process(&mut self, expire_duration: u8) -> Result<ExpireDurationUpdated> {
+        let global_config = &mut self.global_config;
+
+        global_config.expire_duration = expire_duration;
+        global_config.validate()?;
+
+        Ok(ExpireDurationUpdated {
+            expire_duration: global_config.expire_duration,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::{CONFIG, PAUSE_FEES}, errors::AgioError, events::FeesUpdated, state::Config};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetFees<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG.as_bytes()],
+        bump,
+        constraint = global_config.authority == authority.key() @AgioError::IncorrectAuthority,
+        constraint = !global_config.is_paused(PAUSE_FEES) @AgioError::Paused,
+    )]
+    global_config: Box<Account<'info, Config>>,
+
+    pub authority: Signer<'info>,
+}
+
+impl<'info> SetFees<'info> {
+    pub fn process(
+        &mut self,
+        lend_fee: u64,
+        borrow_fee: u64,
+        liquidation_fee: u64,
+        borrow_fee_on_top: bool,
+    ) -> Result<FeesUpdated> {
+        let global_config = &mut self.global_config;
+
+        global_config.lend_fee = lend_fee;
+        global_config.borrow_fee = borrow_fee;
+        global_config.liquidation_fee = liquidation_fee;
+        global_config.borrow_fee_on_top = borrow_fee_on_top;
+        global_config.validate()?;
+
+        Ok(FeesUpdated {
+            lend_fee: global_config.lend_fee,
+            borrow_fee: global_config.borrow_fee,
+            liquidation_fee: global_config.liquidation_fee,
+            borrow_fee_on_top: global_config.borrow_fee_on_top,
+            max_fee: global_config.max_fee,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
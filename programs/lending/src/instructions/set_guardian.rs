@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::CONFIG, errors::AgioError, events::GuardianUpdated, state::Config};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetGuardian<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG.as_bytes()],
+        bump,
+        constraint = global_config.authority == authority.key() @AgioError::IncorrectAuthority
+    )]
+    global_config: Box<Account<'info, Config>>,
+
+    pub authority: Signer<'info>,
+}
+
+impl<'info> SetGuardian<'info> {
+    pub fn process(&mut self, guardian: Pubkey) -> Result<GuardianUpdated> {
+        let global_config = &mut self.global_config;
+
+        let previous_guardian = global_config.guardian;
+        global_config.guardian = guardian;
+
+        Ok(GuardianUpdated {
+            previous_guardian,
+            guardian: global_config.guardian,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::CONFIG, errors::AgioError, events::LiquidationParamsUpdated, state::Config};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetLiquidationParams<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG.as_bytes()],
+        bump,
+        constraint = global_config.authority == authority.key() @AgioError::IncorrectAuthority
+    )]
+    global_config: Box<Account<'info, Config>>,
+
+    pub authority: Signer<'info>,
+}
+
+impl<'info> SetLiquidationParams<'info> {
+    pub fn process(
+        &mut self,
+        liquidation_threshold_bps: u64,
+        liquidation_bonus_bps: u64,
+    ) -> Result<LiquidationParamsUpdated> {
+        let global_config = &mut self.global_config;
+
+        global_config.liquidation_threshold_bps = liquidation_threshold_bps;
+        global_config.liquidation_bonus_bps = liquidation_bonus_bps;
+        global_config.validate()?;
+
+        Ok(LiquidationParamsUpdated {
+            liquidation_threshold_bps: global_config.liquidation_threshold_bps,
+            liquidation_bonus_bps: global_config.liquidation_bonus_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::{CONFIG, PAUSE_FEES}, errors::AgioError, events::FeesUpdated, state::Config};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetMaxFee<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG.as_bytes()],
+        bump,
+        constraint = global_config.authority == authority.key() @AgioError::IncorrectAuthority,
+        constraint = !global_config.is_paused(PAUSE_FEES) @AgioError::Paused,
+    )]
+    global_config: Box<Account<'info, Config>>,
+
+    pub authority: Signer<'info>,
+}
+
+impl<'info> SetMaxFee<'info> {
+    pub fn process(&mut self, max_fee: u64) -> Result<FeesUpdated> {
+        let global_config = &mut self.global_config;
+
+        //  lowering the cap below a live fee is rejected, fees must come down first
+        global_config.max_fee = max_fee;
+        global_config.validate()?;
+
+        Ok(FeesUpdated {
+            lend_fee: global_config.lend_fee,
+            borrow_fee: global_config.borrow_fee,
+            liquidation_fee: global_config.liquidation_fee,
+            borrow_fee_on_top: global_config.borrow_fee_on_top,
+            max_fee: global_config.max_fee,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
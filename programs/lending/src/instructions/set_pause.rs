@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::CONFIG, errors::AgioError, events::PauseUpdated, state::Config};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetPause<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG.as_bytes()],
+        bump,
+        constraint = global_config.can_pause(&authority.key()) @AgioError::IncorrectAuthority
+    )]
+    global_config: Box<Account<'info, Config>>,
+
+    //  the authority or the guardian
+    pub authority: Signer<'info>,
+}
+
+impl<'info> SetPause<'info> {
+    pub fn process(&mut self, pause_flags: u8) -> Result<PauseUpdated> {
+        let global_config = &mut self.global_config;
+
+        let previous_pause_flags = global_config.pause_flags;
+        global_config.pause_flags = pause_flags;
+        global_config.validate()?;
+
+        Ok(PauseUpdated {
+            previous_pause_flags,
+            pause_flags: global_config.pause_flags,
+            updated_by: self.authority.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::CONFIG, errors::AgioError, events::TeamWalletUpdated, state::Config};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetTeamWallet<'info> {
+    #[account(
+        mut,
+        seeds = [CONFIG.as_bytes()],
+        bump,
+        constraint = global_config.authority == authority.key() @AgioError::IncorrectAuthority
+    )]
+    global_config: Box<Account<'info, Config>>,
+
+    pub authority: Signer<'info>,
+}
+
+impl<'info> SetTeamWallet<'info> {
+    pub fn process(&mut self, team_wallet: Pubkey) -> Result<TeamWalletUpdated> {
+        let global_config = &mut self.global_config;
+
+        let previous_team_wallet = global_config.team_wallet;
+        global_config.team_wallet = team_wallet;
+        global_config.validate()?;
+
+        Ok(TeamWalletUpdated {
+            previous_team_wallet,
+            team_wallet: global_config.team_wallet,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+}
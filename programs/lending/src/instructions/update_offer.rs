@@ -0,0 +1,205 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface}};
+
+use crate::{constants::{ASSET, LOAN}, errors::AgioError, events::OfferUpdated, interest, seeds::loan_signer_seeds, state::{AssetConfig, Loan, LoanStatus}, utils::{is_native_mint, received_amount, sol_transfer_from_program_account, token_close_with_signer, token_transfer_user, token_transfer_with_signer, wrap_sol_from_program_account, wrap_sol_from_user}};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateOffer<'info> {
+
+    #[account(
+        mut,
+        seeds = [LOAN.as_bytes(), &lender.key().to_bytes(), &loan_key.key().to_bytes()],
+        bump,
+    )]
+    loan: Account<'info, Loan>,
+
+    #[account(
+        constraint = loan_mint.key() == loan.loan_mint @AgioError::InvalidLoanMint,
+        mint::token_program = loan_token_program,
+    )]
+    pub loan_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [ASSET.as_bytes(), loan_mint.key().as_ref()],
+        bump,
+    )]
+    loan_asset: Box<Account<'info, AssetConfig>>,
+
+    #[account(
+        mut,
+        seeds = [
+            loan.key().as_ref(),
+            loan_token_program.key().as_ref(),
+            loan_mint.key().as_ref(),
+        ],
+        seeds::program = anchor_spl::associated_token::ID,
+        bump,
+    )]
+    loan_mint_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [
+            lender.key().as_ref(),
+            loan_token_program.key().as_ref(),
+            loan_mint.key().as_ref(),
+        ],
+        bump,
+        seeds::program = anchor_spl::associated_token::ID
+    )]
+    lender_loan_ata: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub lender: Signer<'info>,
+
+    pub loan_key: InterfaceAccount<'info, Mint>,
+
+    pub system_program: Program<'info, System>,
+    pub loan_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> UpdateOffer<'info> {
+    pub fn process(
+        &mut self,
+        loan_amount: u64,
+        interest_rate: u64,
+        duration: u64,
+        collateral_amount: u64,
+        loan_bump: u8,
+    ) -> Result<OfferUpdated> {
+        let clock = Clock::get()?;
+        let current_time = clock.unix_timestamp;
+
+        let loan = &mut self.loan;
+        let loan_mint_ata = &self.loan_mint_ata;
+        let lender_loan_ata = &self.lender_loan_ata;
+
+        // only an offer nobody has taken, and that can still be taken, may change
+        require!(loan.status == LoanStatus::Offered, AgioError::AlreadyLended);
+        require!(!loan.is_collection_offer() || collateral_amount == 1, AgioError::InvalidCollectionTerms);
+        self.loan_asset.require_loan(loan_amount)?;
+        require!(current_time < loan.expire_date, AgioError::AlreadyExpired);
+
+        let native_loan = is_native_mint(&loan.loan_mint);
+
+        let lender_key = loan.lender;
+        let loan_key = self.loan_key.key();
+        let bump = [loan_bump];
+        let signer_seeds = loan_signer_seeds(&lender_key, &loan_key, &bump);
+
+        if loan_amount > loan.loan_amount {
+            let top_up = loan_amount - loan.loan_amount;
+
+            if native_loan {
+                // wrap lender sol into the loan_mint pda ata
+                wrap_sol_from_user(
+                    &self.lender,
+                    loan_mint_ata.to_account_info(),
+                    &self.system_program,
+                    &self.loan_token_program,
+                    top_up
+                )?;
+            } else {
+                // transfer loan_mint lender to pda
+                token_transfer_user(
+                    lender_loan_ata.clone(),
+                    &self.lender,
+                    loan_mint_ata.to_account_info(),
+                    &self.loan_mint,
+                    &self.loan_token_program,
+                    top_up
+                )?;
+            }
+
+            // the vault only holds what is left after the mint's transfer fee
+            loan.loan_amount = loan.loan_amount
+                .checked_add(received_amount(&self.loan_mint, top_up)?)
+                .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+        } else if loan_amount < loan.loan_amount {
+            let withdrawal = loan.loan_amount - loan_amount;
+
+            if native_loan {
+                let loan_info = loan.to_account_info();
+
+                // unwrap the whole vault into the loan pda, pay the withdrawal out and re-wrap the rest
+                token_close_with_signer(
+                    loan_mint_ata.to_account_info(),
+                    loan_info.clone(),
+                    loan_info.clone(),
+                    &self.loan_mint,
+                    &self.loan_token_program,
+                    &[&signer_seeds[..]],
+                )?;
+                sol_transfer_from_program_account(&loan_info, &self.lender.to_account_info(), withdrawal)?;
+
+                anchor_spl::associated_token::create(CpiContext::new(
+                    self.associated_token_program.to_account_info(),
+                    anchor_spl::associated_token::Create {
+                        payer: self.lender.to_account_info(),
+                        associated_token: loan_mint_ata.to_account_info(),
+                        authority: loan_info.clone(),
+
+                        mint: self.loan_mint.to_account_info(),
+                        system_program: self.system_program.to_account_info(),
+                        token_program: self.loan_token_program.to_account_info(),
+                    }
+                ))?;
+                wrap_sol_from_program_account(
+                    &loan_info,
+                    loan_mint_ata.to_account_info(),
+                    &self.loan_token_program,
+                    loan_amount
+                )?;
+            } else {
+                // create lender loan_mint ata if it doesn't exit
+                if lender_loan_ata.data_is_empty() {
+                    anchor_spl::associated_token::create(CpiContext::new(
+                        self.associated_token_program.to_account_info(),
+                        anchor_spl::associated_token::Create {
+                            payer: self.lender.to_account_info(),
+                            associated_token: lender_loan_ata.to_account_info(),
+                            authority: self.lender.to_account_info(),
+
+                            mint: self.loan_mint.to_account_info(),
+                            system_program: self.system_program.to_account_info(),
+                            token_program: self.loan_token_program.to_account_info(),
+                        }
+                    ))?;
+                }
+
+                // transfer loan_mint pda to lender
+                token_transfer_with_signer(
+                    loan_mint_ata.to_account_info(),
+                    loan.to_account_info(),
+                    lender_loan_ata.clone(),
+                    &self.loan_mint,
+                    &self.loan_token_program,
+                    &[&signer_seeds[..]],
+                    withdrawal
+                )?;
+            }
+
+            loan.loan_amount = loan_amount;
+        }
+
+        loan.interest_rate = interest_rate;
+        loan.duration = interest::term_seconds(duration)?;
+        loan.collateral_amount = collateral_amount;
+        loan.offer_version = loan.offer_version
+            .checked_add(1)
+            .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+
+        Ok(OfferUpdated {
+            loan: loan.key(),
+            lender: loan.lender,
+            loan_amount: loan.loan_amount,
+            interest_rate: loan.interest_rate,
+            duration: loan.duration,
+            collateral_amount: loan.collateral_amount,
+            offer_version: loan.offer_version,
+            timestamp: current_time,
+        })
+    }
+}
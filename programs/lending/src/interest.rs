@@ -0,0 +1,144 @@
+use crate::{
+    constants::{BPS_DENOMINATOR, SECONDS_PER_DAY, SECONDS_PER_YEAR},
+    errors::AgioError,
+};
+use anchor_lang::prelude::*;
+
+//  `days` as seconds, refused rather than wrapped when it does not fit an i64
+pub fn days_to_seconds(days: u64) -> Result<i64> {
+    i64::try_from(days)
+        .ok()
+        .and_then(|days| days.checked_mul(SECONDS_PER_DAY))
+        .ok_or(error!(AgioError::OverflowOrUnderflowOccurred))
+}
+
+//  a loan term of `days` in seconds, every term runs for at least one day
+pub fn term_seconds(days: u64) -> Result<i64> {
+    require!(days > 0, AgioError::InvalidDuration);
+    days_to_seconds(days)
+}
+
+//  seconds of interest a loan has earned, capped at the loan duration
+pub fn accrual_seconds(borrow_date: i64, duration: i64, now: i64) -> Result<i64> {
+    let elapsed = now
+        .checked_sub(borrow_date)
+        .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+
+    Ok(elapsed.clamp(0, duration.max(0)))
+}
+
+//  simple interest on `principal` at `rate_bps` per year over `elapsed` seconds
+//  rounded up so the lender is never short-changed by truncation
+pub fn accrued_interest(principal: u64, rate_bps: u64, elapsed: i64) -> Result<u64> {
+    if elapsed <= 0 || rate_bps == 0 || principal == 0 {
+        return Ok(0);
+    }
+
+    let numerator = (principal as u128)
+        .checked_mul(rate_bps as u128)
+        .and_then(|v| v.checked_mul(elapsed as u128))
+        .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+    let denominator = (BPS_DENOMINATOR as u128)
+        .checked_mul(SECONDS_PER_YEAR as u128)
+        .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+
+    let interest = numerator
+        .checked_add(denominator - 1)
+        .ok_or(AgioError::OverflowOrUnderflowOccurred)?
+        / denominator;
+
+    u64::try_from(interest).map_err(|_| error!(AgioError::OverflowOrUnderflowOccurred))
+}
+
+//  interest owed on a loan at `now`
+pub fn interest_due(
+    principal: u64,
+    rate_bps: u64,
+    borrow_date: i64,
+    duration: i64,
+    now: i64,
+) -> Result<u64> {
+    let elapsed = accrual_seconds(borrow_date, duration, now)?;
+    accrued_interest(principal, rate_bps, elapsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = SECONDS_PER_DAY;
+
+    #[test]
+    fn whole_year_accrues_the_full_rate() {
+        assert_eq!(accrued_interest(10_000, 1_000, SECONDS_PER_YEAR).unwrap(), 1_000);
+        assert_eq!(accrued_interest(10_000, 10_000, SECONDS_PER_YEAR).unwrap(), 10_000);
+    }
+
+    #[test]
+    fn fractions_round_up() {
+        //  1 * 1 * 1 / (10_000 * SECONDS_PER_YEAR) is far below one unit
+        assert_eq!(accrued_interest(1, 1, 1).unwrap(), 1);
+        //  1_000 bps on 10_000 over half a day is 1.369..., one unit past the exact 1
+        assert_eq!(accrued_interest(10_000, 1_000, DAY / 2).unwrap(), 2);
+        //  exact results are not bumped
+        assert_eq!(accrued_interest(10_000, 1_000, SECONDS_PER_YEAR / 2).unwrap(), 500);
+    }
+
+    #[test]
+    fn zero_inputs_accrue_nothing() {
+        assert_eq!(accrued_interest(0, 1_000, DAY).unwrap(), 0);
+        assert_eq!(accrued_interest(10_000, 0, DAY).unwrap(), 0);
+        assert_eq!(accrued_interest(10_000, 1_000, 0).unwrap(), 0);
+        assert_eq!(accrued_interest(10_000, 1_000, -DAY).unwrap(), 0);
+    }
+
+    #[test]
+    fn zero_duration_accrues_nothing() {
+        assert_eq!(accrual_seconds(1_000, 0, 1_000 + DAY).unwrap(), 0);
+        assert_eq!(interest_due(10_000, 1_000, 1_000, 0, 1_000 + DAY).unwrap(), 0);
+        //  a negative duration is treated as zero
+        assert_eq!(accrual_seconds(1_000, -DAY, 1_000 + DAY).unwrap(), 0);
+    }
+
+    #[test]
+    fn accrual_is_clamped_to_the_loan_term() {
+        //  before the borrow date nothing has accrued
+        assert_eq!(accrual_seconds(1_000, DAY, 0).unwrap(), 0);
+        assert_eq!(accrual_seconds(1_000, DAY, 1_000 + DAY / 2).unwrap(), DAY / 2);
+        //  past maturity interest stops at the duration
+        assert_eq!(accrual_seconds(1_000, DAY, 1_000 + 10 * DAY).unwrap(), DAY);
+        assert_eq!(
+            interest_due(10_000, 1_000, 0, SECONDS_PER_YEAR, 3 * SECONDS_PER_YEAR).unwrap(),
+            1_000,
+        );
+    }
+
+    #[test]
+    fn max_duration() {
+        assert_eq!(accrual_seconds(0, i64::MAX, i64::MAX).unwrap(), i64::MAX);
+        assert!(accrual_seconds(i64::MIN, i64::MAX, 1).is_err());
+
+        //  the largest principal over one year at 100% still fits in a u64
+        assert_eq!(accrued_interest(u64::MAX, 10_000, SECONDS_PER_YEAR).unwrap(), u64::MAX);
+        //  anything more does not and is refused instead of wrapping
+        assert!(accrued_interest(u64::MAX, 10_000, SECONDS_PER_YEAR + 1).is_err());
+        assert!(accrued_interest(u64::MAX, u64::MAX, i64::MAX).is_err());
+        assert!(interest_due(u64::MAX, 10_000, 0, i64::MAX, i64::MAX).is_err());
+    }
+
+    #[test]
+    fn term_is_at_least_a_day_and_never_wraps() {
+        assert_eq!(term_seconds(1).unwrap(), DAY);
+        assert_eq!(term_seconds(30).unwrap(), 30 * DAY);
+        assert_eq!(term_seconds(0).unwrap_err(), AgioError::InvalidDuration.into());
+        //  u64::MAX days would wrap negative through `as i64`
+        assert_eq!(term_seconds(u64::MAX).unwrap_err(), AgioError::OverflowOrUnderflowOccurred.into());
+        assert_eq!(term_seconds(i64::MAX as u64).unwrap_err(), AgioError::OverflowOrUnderflowOccurred.into());
+
+        let max_days = (i64::MAX / DAY) as u64;
+        assert_eq!(term_seconds(max_days).unwrap(), max_days as i64 * DAY);
+        assert!(term_seconds(max_days + 1).is_err());
+        //  an extension allowance may be zero
+        assert_eq!(days_to_seconds(0).unwrap(), 0);
+    }
+}
@@ -0,0 +1,219 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::BPS_DENOMINATOR, state::{Config, Loan, LoanStatus, PriceTerms}};
+
+//  account layouts of the originally deployed program, before Loan and Config carried
+//  a version byte, read by migrate_loan and migrate_config only, never change these
+
+#[derive(AnchorDeserialize, Clone, Debug)]
+pub struct ConfigV0 {
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+    pub team_wallet: Pubkey,
+    pub lend_fee: u64,
+    pub borrow_fee: u64,
+    pub expire_duration: u8,
+}
+
+impl From<ConfigV0> for Config {
+    //  fields the old program did not have start at their neutral value: no liquidation
+    //  fee or fallback threshold, no guardian, nothing paused and the widest legal max_fee
+    fn from(config: ConfigV0) -> Self {
+        Config {
+            version: Config::VERSION,
+            authority: config.authority,
+            pending_authority: config.pending_authority,
+            team_wallet: config.team_wallet,
+            lend_fee: config.lend_fee,
+            borrow_fee: config.borrow_fee,
+            liquidation_fee: 0,
+            borrow_fee_on_top: false,
+            max_fee: BPS_DENOMINATOR,
+            expire_duration: config.expire_duration,
+            liquidation_threshold_bps: 0,
+            liquidation_bonus_bps: 0,
+            guardian: Pubkey::default(),
+            pause_flags: 0,
+            reserved: [0; 64],
+        }
+    }
+}
+
+#[derive(AnchorDeserialize, Clone, Debug)]
+pub struct LoanV0 {
+    pub lender: Pubkey,
+    pub borrower: Pubkey,
+    pub loan_mint: Pubkey,
+    pub loan_amount: u64,
+    pub interest_rate: u64,
+    pub duration: i64,
+    pub collateral_mint: Pubkey,
+    pub collateral_amount: u64,
+    pub create_date: i64,
+    pub expire_date: i64,
+    pub borrow_date: i64,
+    pub repaid: bool,
+}
+
+impl LoanV0 {
+    //  the old program only knew a repaid flag, an offer nobody borrowed yet has no borrower
+    pub fn status(&self) -> LoanStatus {
+        if self.repaid {
+            LoanStatus::Repaid
+        } else if self.borrower == Pubkey::default() {
+            LoanStatus::Offered
+        } else {
+            LoanStatus::Active
+        }
+    }
+}
+
+impl From<LoanV0> for Loan {
+    fn from(loan: LoanV0) -> Self {
+        let status = loan.status();
+        //  nothing was ever partially repaid, an active loan still owes its whole principal
+        //  and accrues from the day it was borrowed
+        let principal_outstanding = if status == LoanStatus::Active { loan.loan_amount } else { 0 };
+
+        Loan {
+            version: Loan::VERSION,
+            lender: loan.lender,
+            borrower: loan.borrower,
+            loan_mint: loan.loan_mint,
+            loan_amount: loan.loan_amount,
+            interest_rate: loan.interest_rate,
+            duration: loan.duration,
+            collateral_mint: loan.collateral_mint,
+            collateral_amount: loan.collateral_amount,
+            collateral_collection: Pubkey::default(),
+            max_extension: 0,
+            price_terms: PriceTerms::default(),
+            create_date: loan.create_date,
+            expire_date: loan.expire_date,
+            borrow_date: loan.borrow_date,
+            status,
+            offer_version: 0,
+            principal_outstanding,
+            interest_paid: 0,
+            last_payment_ts: loan.borrow_date,
+            lender_slot: 0,
+            borrower_slot: 0,
+            reserved: [0; 48],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //  fixtures are written field by field in the deployed borsh layout rather than through
+    //  the structs above, so a change to either struct is caught here
+
+    fn key(byte: u8) -> Pubkey {
+        Pubkey::new_from_array([byte; 32])
+    }
+
+    fn config_fixture() -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(key(1).as_ref());
+        data.extend_from_slice(key(2).as_ref());
+        data.extend_from_slice(key(3).as_ref());
+        data.extend_from_slice(&150u64.to_le_bytes());
+        data.extend_from_slice(&250u64.to_le_bytes());
+        data.push(7);
+        data
+    }
+
+    //  the old program sized loans with size_of::<Loan>(), 185 bytes of fields padded to 192
+    fn loan_fixture(borrower: Pubkey, repaid: bool) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(key(4).as_ref());
+        data.extend_from_slice(borrower.as_ref());
+        data.extend_from_slice(key(6).as_ref());
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+        data.extend_from_slice(&500u64.to_le_bytes());
+        data.extend_from_slice(&86_400i64.to_le_bytes());
+        data.extend_from_slice(key(7).as_ref());
+        data.extend_from_slice(&3u64.to_le_bytes());
+        data.extend_from_slice(&100i64.to_le_bytes());
+        data.extend_from_slice(&200i64.to_le_bytes());
+        data.extend_from_slice(&150i64.to_le_bytes());
+        data.push(repaid as u8);
+        assert_eq!(data.len(), 185);
+        data.resize(192, 0);
+        data
+    }
+
+    fn migrate_loan(data: &[u8]) -> Loan {
+        Loan::from(LoanV0::deserialize(&mut &data[..]).unwrap())
+    }
+
+    #[test]
+    fn config_v0_reads_the_deployed_layout() {
+        let data = config_fixture();
+        assert_eq!(data.len(), 113);
+
+        let config = Config::from(ConfigV0::deserialize(&mut &data[..]).unwrap());
+        assert_eq!(config.version, Config::VERSION);
+        assert_eq!(config.authority, key(1));
+        assert_eq!(config.pending_authority, key(2));
+        assert_eq!(config.team_wallet, key(3));
+        assert_eq!(config.lend_fee, 150);
+        assert_eq!(config.borrow_fee, 250);
+        assert_eq!(config.expire_duration, 7);
+        assert_eq!(config.liquidation_fee, 0);
+        assert!(!config.borrow_fee_on_top);
+        assert_eq!(config.max_fee, BPS_DENOMINATOR);
+        assert_eq!(config.liquidation_threshold_bps, 0);
+        assert_eq!(config.liquidation_bonus_bps, 0);
+        assert_eq!(config.guardian, Pubkey::default());
+        assert_eq!(config.pause_flags, 0);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn config_v0_rejects_a_truncated_account() {
+        let data = config_fixture();
+        assert!(ConfigV0::deserialize(&mut &data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn loan_v0_borrowed_becomes_active() {
+        let loan = migrate_loan(&loan_fixture(key(5), false));
+        assert_eq!(loan.version, Loan::VERSION);
+        assert_eq!(loan.lender, key(4));
+        assert_eq!(loan.borrower, key(5));
+        assert_eq!(loan.loan_mint, key(6));
+        assert_eq!(loan.loan_amount, 1_000);
+        assert_eq!(loan.interest_rate, 500);
+        assert_eq!(loan.duration, 86_400);
+        assert_eq!(loan.collateral_mint, key(7));
+        assert_eq!(loan.collateral_amount, 3);
+        assert_eq!(loan.create_date, 100);
+        assert_eq!(loan.expire_date, 200);
+        assert_eq!(loan.borrow_date, 150);
+        assert_eq!(loan.status, LoanStatus::Active);
+        assert_eq!(loan.principal_outstanding, 1_000);
+        assert_eq!(loan.interest_paid, 0);
+        assert_eq!(loan.last_payment_ts, 150);
+        assert_eq!(loan.collateral_collection, Pubkey::default());
+        assert_eq!(loan.price_terms, PriceTerms::default());
+        assert_eq!(loan.offer_version, 0);
+        assert_eq!((loan.lender_slot, loan.borrower_slot), (0, 0));
+    }
+
+    #[test]
+    fn loan_v0_without_borrower_stays_offered() {
+        let loan = migrate_loan(&loan_fixture(Pubkey::default(), false));
+        assert_eq!(loan.status, LoanStatus::Offered);
+        assert_eq!(loan.principal_outstanding, 0);
+    }
+
+    #[test]
+    fn loan_v0_repaid_becomes_repaid() {
+        let loan = migrate_loan(&loan_fixture(key(5), true));
+        assert_eq!(loan.status, LoanStatus::Repaid);
+        assert_eq!(loan.principal_outstanding, 0);
+    }
+}
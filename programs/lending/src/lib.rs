@@ -1,12 +1,18 @@
 pub mod errors;
+pub mod events;
 pub mod state;
 pub mod instructions;
 pub mod constants;
 pub mod utils;
+pub mod interest;
+pub mod fees;
+pub mod seeds;
+pub mod oracle;
+pub mod legacy;
+pub mod portfolio;
 
-use crate::instructions::*;
+use crate::{instructions::*, state::{AssetParams, PriceTerms}};
 use anchor_lang::prelude::*;
-use state::Config;
 
 declare_id!("EZZtc7TU4Dd5Bc1wdQZ9szhsv3cavuHzNCy8Laq1beLU");
 
@@ -14,26 +20,202 @@ declare_id!("EZZtc7TU4Dd5Bc1wdQZ9szhsv3cavuHzNCy8Laq1beLU");
 pub mod lending {
     use super::*;
 
-    //  called by admin to set global config
-    //  need to check the signer is authority
-    pub fn configure(ctx: Context<Configure>, new_config: Config) -> Result<()> {
-        ctx.accounts.process(new_config, ctx.bumps.config)
+    //  called once by the program's upgrade authority to create the global config
+    //  fees are in basis points, expire_duration in days
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        team_wallet: Pubkey,
+        lend_fee: u64,
+        borrow_fee: u64,
+        liquidation_fee: u64,
+        max_fee: u64,
+        expire_duration: u8,
+    ) -> Result<()> {
+        let event = ctx.accounts.process(team_wallet, lend_fee, borrow_fee, liquidation_fee, max_fee, expire_duration)?;
+        emit_cpi!(event);
+        Ok(())
     }
 
-    pub fn create_loan(ctx: Context<CreateLoan>, loan_amount: u64, interest_rate: u64, duration: u64, collateral_amount: u64) -> Result<()> {
-        ctx.accounts.process(loan_amount, interest_rate, duration, collateral_amount)
+    pub fn set_fees(
+        ctx: Context<SetFees>,
+        lend_fee: u64,
+        borrow_fee: u64,
+        liquidation_fee: u64,
+        borrow_fee_on_top: bool,
+    ) -> Result<()> {
+        let event = ctx.accounts.process(lend_fee, borrow_fee, liquidation_fee, borrow_fee_on_top)?;
+        emit_cpi!(event);
+        Ok(())
     }
 
-    pub fn borrow_loan(ctx: Context<BorrowLoan>, collateral_amount: u64) -> Result<()> {
-        ctx.accounts.process(collateral_amount, ctx.bumps.loan)
+    pub fn set_max_fee(ctx: Context<SetMaxFee>, max_fee: u64) -> Result<()> {
+        let event = ctx.accounts.process(max_fee)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn set_team_wallet(ctx: Context<SetTeamWallet>, team_wallet: Pubkey) -> Result<()> {
+        let event = ctx.accounts.process(team_wallet)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn set_expire_duration(ctx: Context<SetExpireDuration>, expire_duration: u8) -> Result<()> {
+        let event = ctx.accounts.process(expire_duration)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    //  liquidation_threshold_bps of 0 leaves liquidation to loans that set their own
+    pub fn set_liquidation_params(ctx: Context<SetLiquidationParams>, liquidation_threshold_bps: u64, liquidation_bonus_bps: u64) -> Result<()> {
+        let event = ctx.accounts.process(liquidation_threshold_bps, liquidation_bonus_bps)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    //  called by admin, the guardian may set pause flags but nothing else
+    pub fn set_guardian(ctx: Context<SetGuardian>, guardian: Pubkey) -> Result<()> {
+        let event = ctx.accounts.process(guardian)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    //  called by admin or guardian, pause_flags is a set of PAUSE_* bits and replaces the current one
+    pub fn set_pause(ctx: Context<SetPause>, pause_flags: u8) -> Result<()> {
+        let event = ctx.accounts.process(pause_flags)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    //  called by admin to enable, restrict or deny a mint, creating its record on first use
+    //  clearing both allowed flags denylists the mint for new loans
+    pub fn set_asset_config(ctx: Context<SetAssetConfig>, params: AssetParams) -> Result<()> {
+        let event = ctx.accounts.process(params)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    //  called by admin once after an upgrade to move the config onto the versioned layout
+    pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
+        let event = ctx.accounts.process()?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    //  called by admin to start handing authority over to new_authority
+    pub fn nominate_authority(ctx: Context<NominateAuthority>, new_authority: Pubkey) -> Result<()> {
+        let event = ctx.accounts.process(new_authority)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    //  called by the nominated key to complete the handover
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let event = ctx.accounts.process()?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    //  called by admin to withdraw a pending nomination
+    pub fn cancel_authority_nomination(ctx: Context<CancelAuthorityNomination>) -> Result<()> {
+        let event = ctx.accounts.process()?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    //  interest_rate is in basis points per year, duration and max_extension in days
+    //  price_terms with a min_collateral_ratio_bps of 0 leave the offer unpriced
+    //  collateral_is_collection makes collateral_mint a verified collection, any one of its nfts is accepted
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_loan(ctx: Context<CreateLoan>, loan_amount: u64, interest_rate: u64, duration: u64, collateral_amount: u64, max_extension: u64, price_terms: PriceTerms, collateral_is_collection: bool) -> Result<()> {
+        let event = ctx.accounts.process(loan_amount, interest_rate, duration, collateral_amount, max_extension, price_terms, collateral_is_collection)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    //  called by lender to change an offer nobody has borrowed yet, duration in days
+    //  loan_amount above or below the current principal tops the vault up or withdraws from it
+    pub fn update_offer(ctx: Context<UpdateOffer>, loan_amount: u64, interest_rate: u64, duration: u64, collateral_amount: u64) -> Result<()> {
+        let event = ctx.accounts.process(loan_amount, interest_rate, duration, collateral_amount, ctx.bumps.loan)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    //  offer_version must match the offer the borrower agreed to
+    pub fn borrow_loan(ctx: Context<BorrowLoan>, collateral_amount: u64, offer_version: u64) -> Result<()> {
+        let event = ctx.accounts.process(collateral_amount, offer_version, ctx.bumps.loan)?;
+        emit_cpi!(event);
+        Ok(())
     }
 
     pub fn cancel_loan(ctx: Context<CancelLoan>) -> Result<()> {
-        ctx.accounts.process(ctx.bumps.loan)
+        let event = ctx.accounts.process(ctx.bumps.loan)?;
+        emit_cpi!(event);
+        Ok(())
     }
 
     pub fn repay_loan(ctx: Context<RepayLoan>) -> Result<()> {
-        ctx.accounts.process(ctx.bumps.loan)
+        let event = ctx.accounts.process(ctx.bumps.loan)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    //  pays accrued interest first, then principal, releasing collateral pro rata
+    pub fn repay_partial(ctx: Context<RepayPartial>, amount: u64) -> Result<()> {
+        let event = ctx.accounts.process(amount, ctx.bumps.loan)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    //  duration is the new term in days counted from now, interest_rate in basis points per year
+    //  the lender co-signs to accept, otherwise the offer's max_extension must cover it
+    pub fn extend_loan(ctx: Context<ExtendLoan>, duration: u64, interest_rate: u64) -> Result<()> {
+        let event = ctx.accounts.process(duration, interest_rate)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    //  called by lender once an unrepaid loan passes borrow_date + duration
+    pub fn claim_collateral(ctx: Context<ClaimCollateral>) -> Result<()> {
+        let event = ctx.accounts.process(ctx.bumps.loan)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    //  permissionless, anyone may repay an undercollateralised active loan and take its collateral
+    pub fn liquidate(ctx: Context<Liquidate>) -> Result<()> {
+        let event = ctx.accounts.process(ctx.bumps.loan)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    //  called by borrower to lock collateral and ask for loan_amount of loan_mint
+    //  max_interest_rate is in basis points per year, duration in days
+    pub fn create_request(ctx: Context<CreateRequest>, loan_amount: u64, max_interest_rate: u64, duration: u64, collateral_amount: u64) -> Result<()> {
+        let event = ctx.accounts.process(loan_amount, max_interest_rate, duration, collateral_amount)?;
+        emit_cpi!(event);
+        Ok(())
     }
 
+    //  called by lender to fund a request at interest_rate, at most the requested rate
+    pub fn fill_request(ctx: Context<FillRequest>, interest_rate: u64) -> Result<()> {
+        let event = ctx.accounts.process(interest_rate, ctx.bumps.request)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    //  called by borrower to withdraw an unfilled request and its collateral
+    pub fn cancel_request(ctx: Context<CancelRequest>) -> Result<()> {
+        let event = ctx.accounts.process(ctx.bumps.request)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    //  permissionless, moves a loan written by the original program and its vaults to the
+    //  canonical loan address on the versioned layout
+    pub fn migrate_loan(ctx: Context<MigrateLoan>) -> Result<()> {
+        let event = ctx.accounts.process(ctx.bumps.legacy_loan)?;
+        emit_cpi!(event);
+        Ok(())
+    }
 }
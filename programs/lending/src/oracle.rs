@@ -0,0 +1,393 @@
+use anchor_lang::prelude::*;
+use std::cmp::Ordering;
+
+use crate::{
+    constants::{BPS_DENOMINATOR, MAX_PRICE_AGE, MAX_PRICE_CONF_BPS},
+    errors::AgioError,
+};
+
+//  pyth solana receiver, owner of every PriceUpdateV2 account
+pub const PYTH_RECEIVER_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+
+//  sha256("account:PriceUpdateV2")[..8]
+pub const PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+//  mirrors pyth_solana_receiver_sdk::price_update::VerificationLevel
+#[derive(AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VerificationLevel {
+    Partial { num_signatures: u8 },
+    Full,
+}
+
+//  mirrors pythnet_sdk::messages::PriceFeedMessage
+#[derive(AnchorDeserialize, Clone, Copy, Debug)]
+pub struct PriceFeedMessage {
+    pub feed_id: [u8; 32],
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    pub prev_publish_time: i64,
+    pub ema_price: i64,
+    pub ema_conf: u64,
+}
+
+//  mirrors pyth_solana_receiver_sdk::price_update::PriceUpdateV2, parsed by hand
+//  so the program does not depend on the receiver sdk
+#[derive(AnchorDeserialize, Clone, Copy, Debug)]
+pub struct PriceUpdateV2 {
+    pub write_authority: Pubkey,
+    pub verification_level: VerificationLevel,
+    pub price_message: PriceFeedMessage,
+    pub posted_slot: u64,
+}
+
+impl PriceUpdateV2 {
+    pub fn try_from_slice_checked(data: &[u8]) -> Result<Self> {
+        require!(
+            data.len() >= 8 && data[..8] == PRICE_UPDATE_V2_DISCRIMINATOR,
+            AgioError::InvalidPriceAccount
+        );
+        let mut body = &data[8..];
+        PriceUpdateV2::deserialize(&mut body).map_err(|_| error!(AgioError::InvalidPriceAccount))
+    }
+
+    //  fully verified, for the expected feed, fresh and tight enough to lend against
+    pub fn price_no_older_than(&self, feed_id: &[u8; 32], now: i64) -> Result<OraclePrice> {
+        let message = &self.price_message;
+
+        require!(self.verification_level == VerificationLevel::Full, AgioError::InvalidPriceAccount);
+        require!(message.feed_id == *feed_id, AgioError::InvalidPriceFeed);
+
+        let age = now
+            .checked_sub(message.publish_time)
+            .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+        require!(age <= MAX_PRICE_AGE, AgioError::StalePrice);
+
+        require!(message.price > 0, AgioError::InvalidPrice);
+        let price = message.price as u64;
+        let max_conf = (price as u128)
+            .checked_mul(MAX_PRICE_CONF_BPS as u128)
+            .ok_or(AgioError::OverflowOrUnderflowOccurred)?
+            / BPS_DENOMINATOR as u128;
+        require!(message.conf as u128 <= max_conf, AgioError::PriceConfidenceTooWide);
+
+        Ok(OraclePrice {
+            price,
+            conf: message.conf,
+            exponent: message.exponent,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct OraclePrice {
+    pub price: u64,
+    pub conf: u64,
+    pub exponent: i32,
+}
+
+impl OraclePrice {
+    //  lowest price within the confidence interval, used to value collateral
+    pub fn low(&self) -> u64 {
+        self.price.saturating_sub(self.conf)
+    }
+
+    //  highest price within the confidence interval, used to value debt
+    pub fn high(&self) -> u64 {
+        self.price.saturating_add(self.conf)
+    }
+}
+
+//  read a price for `feed_id` out of a PriceUpdateV2 account owned by the pyth receiver
+pub fn load_price(price_update: &AccountInfo, feed_id: &[u8; 32], now: i64) -> Result<OraclePrice> {
+    require!(*price_update.owner == PYTH_RECEIVER_ID, AgioError::InvalidPriceAccount);
+    let data = price_update.try_borrow_data()?;
+    PriceUpdateV2::try_from_slice_checked(&data)?.price_no_older_than(feed_id, now)
+}
+
+//  amount * price scaled to a common power of ten, returned as (mantissa, exponent)
+fn value(amount: u64, price: u64, exponent: i32, decimals: u8) -> Result<(u128, i32)> {
+    let mantissa = (amount as u128)
+        .checked_mul(price as u128)
+        .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+    let exponent = exponent
+        .checked_sub(decimals as i32)
+        .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+    Ok((mantissa, exponent))
+}
+
+fn scale(mantissa: u128, by: u32) -> Result<u128> {
+    10u128
+        .checked_pow(by)
+        .and_then(|factor| mantissa.checked_mul(factor))
+        .ok_or(error!(AgioError::OverflowOrUnderflowOccurred))
+}
+
+//  compares lhs * lhs_factor with rhs * rhs_factor, bringing both to the smaller exponent first
+fn compare(
+    (lhs, lhs_exponent): (u128, i32),
+    lhs_factor: u64,
+    (rhs, rhs_exponent): (u128, i32),
+    rhs_factor: u64,
+) -> Result<Ordering> {
+    let mut lhs = lhs
+        .checked_mul(lhs_factor as u128)
+        .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+    let mut rhs = rhs
+        .checked_mul(rhs_factor as u128)
+        .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+
+    let shift = lhs_exponent - rhs_exponent;
+    if shift > 0 {
+        lhs = scale(lhs, shift as u32)?;
+    } else if shift < 0 {
+        rhs = scale(rhs, shift.unsigned_abs())?;
+    }
+
+    Ok(lhs.cmp(&rhs))
+}
+
+//  collateral_value / loan_value >= ratio_bps / BPS_DENOMINATOR
+//  collateral is valued at the bottom of its confidence interval and debt at the top
+pub fn meets_collateral_ratio(
+    collateral_amount: u64,
+    collateral_price: &OraclePrice,
+    collateral_decimals: u8,
+    loan_amount: u64,
+    loan_price: &OraclePrice,
+    loan_decimals: u8,
+    ratio_bps: u64,
+) -> Result<bool> {
+    let collateral_value = value(
+        collateral_amount,
+        collateral_price.low(),
+        collateral_price.exponent,
+        collateral_decimals,
+    )?;
+    let loan_value = value(loan_amount, loan_price.high(), loan_price.exponent, loan_decimals)?;
+
+    Ok(compare(collateral_value, BPS_DENOMINATOR, loan_value, ratio_bps)? != Ordering::Less)
+}
+
+//  loan_value / collateral_value > threshold_bps / BPS_DENOMINATOR
+//  both sides use the mid price so a borrower is not liquidated on noise
+pub fn exceeds_loan_to_value(
+    loan_amount: u64,
+    loan_price: &OraclePrice,
+    loan_decimals: u8,
+    collateral_amount: u64,
+    collateral_price: &OraclePrice,
+    collateral_decimals: u8,
+    threshold_bps: u64,
+) -> Result<bool> {
+    let loan_value = value(loan_amount, loan_price.price, loan_price.exponent, loan_decimals)?;
+    let collateral_value = value(
+        collateral_amount,
+        collateral_price.price,
+        collateral_price.exponent,
+        collateral_decimals,
+    )?;
+
+    Ok(compare(loan_value, BPS_DENOMINATOR, collateral_value, threshold_bps)? == Ordering::Greater)
+}
+
+//  `amount` of the `from` asset expressed in the `to` asset at mid prices, rounded down
+pub fn convert_amount(
+    amount: u64,
+    from_price: &OraclePrice,
+    from_decimals: u8,
+    to_price: &OraclePrice,
+    to_decimals: u8,
+) -> Result<u64> {
+    let (mut numerator, from_exponent) = value(amount, from_price.price, from_price.exponent, from_decimals)?;
+    let mut denominator = to_price.price as u128;
+    let to_exponent = to_price
+        .exponent
+        .checked_sub(to_decimals as i32)
+        .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+
+    let shift = from_exponent - to_exponent;
+    if shift > 0 {
+        numerator = scale(numerator, shift as u32)?;
+    } else if shift < 0 {
+        denominator = scale(denominator, shift.unsigned_abs())?;
+    }
+
+    u64::try_from(numerator / denominator).map_err(|_| error!(AgioError::OverflowOrUnderflowOccurred))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::hash::hash;
+
+    const FEED: [u8; 32] = [7; 32];
+    const NOW: i64 = 1_700_000_000;
+
+    struct Fixture {
+        feed_id: [u8; 32],
+        full: bool,
+        price: i64,
+        conf: u64,
+        exponent: i32,
+        publish_time: i64,
+    }
+
+    impl Default for Fixture {
+        fn default() -> Self {
+            Fixture { feed_id: FEED, full: true, price: 15_000_000_000, conf: 0, exponent: -8, publish_time: NOW }
+        }
+    }
+
+    impl Fixture {
+        //  PriceUpdateV2 account data in the receiver's borsh layout, written field by field
+        fn data(&self) -> Vec<u8> {
+            let mut data = PRICE_UPDATE_V2_DISCRIMINATOR.to_vec();
+            data.extend_from_slice(&[9; 32]);
+            if self.full {
+                data.push(1);
+            } else {
+                data.extend_from_slice(&[0, 3]);
+            }
+            data.extend_from_slice(&self.feed_id);
+            data.extend_from_slice(&self.price.to_le_bytes());
+            data.extend_from_slice(&self.conf.to_le_bytes());
+            data.extend_from_slice(&self.exponent.to_le_bytes());
+            data.extend_from_slice(&self.publish_time.to_le_bytes());
+            data.extend_from_slice(&(self.publish_time - 1).to_le_bytes());
+            data.extend_from_slice(&self.price.to_le_bytes());
+            data.extend_from_slice(&self.conf.to_le_bytes());
+            data.extend_from_slice(&42u64.to_le_bytes());
+            data
+        }
+
+        fn load(&self, now: i64) -> Result<OraclePrice> {
+            load_account(&PYTH_RECEIVER_ID, &mut self.data(), now)
+        }
+    }
+
+    fn load_account(owner: &Pubkey, data: &mut [u8], now: i64) -> Result<OraclePrice> {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let info = AccountInfo::new(&key, false, false, &mut lamports, data, owner, false, 0);
+        load_price(&info, &FEED, now)
+    }
+
+    fn price(price: u64, conf: u64, exponent: i32) -> OraclePrice {
+        OraclePrice { price, conf, exponent }
+    }
+
+    #[test]
+    fn discriminator_matches_the_receiver_account_name() {
+        assert_eq!(hash(b"account:PriceUpdateV2").to_bytes()[..8], PRICE_UPDATE_V2_DISCRIMINATOR);
+    }
+
+    #[test]
+    fn loads_a_fresh_fully_verified_price() {
+        let loaded = Fixture { conf: 1_000, ..Fixture::default() }.load(NOW).unwrap();
+        assert_eq!((loaded.price, loaded.conf, loaded.exponent), (15_000_000_000, 1_000, -8));
+    }
+
+    #[test]
+    fn rejects_accounts_not_owned_by_the_receiver() {
+        let result = load_account(&Pubkey::new_unique(), &mut Fixture::default().data(), NOW);
+        assert_eq!(result.unwrap_err(), AgioError::InvalidPriceAccount.into());
+    }
+
+    #[test]
+    fn rejects_a_wrong_discriminator_or_truncated_body() {
+        let mut data = Fixture::default().data();
+        data[0] ^= 1;
+        assert_eq!(load_account(&PYTH_RECEIVER_ID, &mut data, NOW).unwrap_err(), AgioError::InvalidPriceAccount.into());
+
+        let mut data = Fixture::default().data();
+        data.truncate(60);
+        assert_eq!(load_account(&PYTH_RECEIVER_ID, &mut data, NOW).unwrap_err(), AgioError::InvalidPriceAccount.into());
+
+        assert_eq!(load_account(&PYTH_RECEIVER_ID, &mut [], NOW).unwrap_err(), AgioError::InvalidPriceAccount.into());
+    }
+
+    #[test]
+    fn rejects_partially_verified_updates() {
+        let fixture = Fixture { full: false, ..Fixture::default() };
+        assert_eq!(fixture.load(NOW).unwrap_err(), AgioError::InvalidPriceAccount.into());
+    }
+
+    #[test]
+    fn rejects_another_feed() {
+        let fixture = Fixture { feed_id: [8; 32], ..Fixture::default() };
+        assert_eq!(fixture.load(NOW).unwrap_err(), AgioError::InvalidPriceFeed.into());
+    }
+
+    #[test]
+    fn staleness() {
+        let fixture = Fixture::default();
+        assert!(fixture.load(NOW + MAX_PRICE_AGE).is_ok());
+        assert_eq!(fixture.load(NOW + MAX_PRICE_AGE + 1).unwrap_err(), AgioError::StalePrice.into());
+        //  a publish time slightly ahead of the validator clock is not stale
+        assert!(fixture.load(NOW - 5).is_ok());
+    }
+
+    #[test]
+    fn rejects_non_positive_prices() {
+        for bad in [0, -1] {
+            let fixture = Fixture { price: bad, ..Fixture::default() };
+            assert_eq!(fixture.load(NOW).unwrap_err(), AgioError::InvalidPrice.into());
+        }
+    }
+
+    #[test]
+    fn confidence_bound() {
+        //  MAX_PRICE_CONF_BPS of the price is the widest interval accepted
+        let max_conf = 15_000_000_000 * MAX_PRICE_CONF_BPS / BPS_DENOMINATOR;
+        assert!(Fixture { conf: max_conf, ..Fixture::default() }.load(NOW).is_ok());
+        assert_eq!(
+            Fixture { conf: max_conf + 1, ..Fixture::default() }.load(NOW).unwrap_err(),
+            AgioError::PriceConfidenceTooWide.into(),
+        );
+    }
+
+    #[test]
+    fn collateral_ratio_scales_exponents_and_decimals() {
+        //  1 SOL (9 decimals) at $150 against 100 USDC (6 decimals) at $1 is exactly 150%
+        let sol = price(15_000_000_000, 0, -8);
+        let usdc = price(100_000_000, 0, -8);
+        assert!(meets_collateral_ratio(1_000_000_000, &sol, 9, 100_000_000, &usdc, 6, 15_000).unwrap());
+        assert!(!meets_collateral_ratio(1_000_000_000, &sol, 9, 100_000_000, &usdc, 6, 15_001).unwrap());
+
+        //  the same prices quoted with other exponents compare the same way
+        let usdc_e6 = price(1_000_000, 0, -6);
+        let sol_e5 = price(15_000_000, 0, -5);
+        assert!(meets_collateral_ratio(1_000_000_000, &sol_e5, 9, 100_000_000, &usdc_e6, 6, 15_000).unwrap());
+        assert!(!meets_collateral_ratio(1_000_000_000, &sol_e5, 9, 100_000_000, &usdc_e6, 6, 15_001).unwrap());
+
+        //  decimals, not raw amounts, set each side's value
+        assert!(meets_collateral_ratio(1_000_000, &sol, 6, 100_000_000_000, &usdc, 9, 15_000).unwrap());
+        assert!(!meets_collateral_ratio(1_000_000_000, &sol, 10, 100_000_000, &usdc, 6, 15_000).unwrap());
+    }
+
+    #[test]
+    fn collateral_ratio_uses_the_pessimistic_end_of_each_interval() {
+        //  any confidence on either side tips an exact 150% below the bar
+        let usdc = price(100_000_000, 0, -8);
+        let sol = price(15_000_000_000, 0, -8);
+        let sol_wide = price(15_000_000_000, 1, -8);
+        let usdc_wide = price(100_000_000, 1, -8);
+        assert!(!meets_collateral_ratio(1_000_000_000, &sol_wide, 9, 100_000_000, &usdc, 6, 15_000).unwrap());
+        assert!(!meets_collateral_ratio(1_000_000_000, &sol, 9, 100_000_000, &usdc_wide, 6, 15_000).unwrap());
+    }
+
+    #[test]
+    fn loan_to_value_and_conversion_use_mid_prices() {
+        let sol = price(15_000_000_000, 1_000_000, -8);
+        let usdc = price(100_000_000, 1_000, -8);
+        //  100 USDC against 1 SOL is a 66.66% loan to value
+        assert!(exceeds_loan_to_value(100_000_000, &usdc, 6, 1_000_000_000, &sol, 9, 6_666).unwrap());
+        assert!(!exceeds_loan_to_value(100_000_000, &usdc, 6, 1_000_000_000, &sol, 9, 6_667).unwrap());
+        //  150 USDC buys exactly 1 SOL, rounding down below that
+        assert_eq!(convert_amount(150_000_000, &usdc, 6, &sol, 9).unwrap(), 1_000_000_000);
+        //  a millionth of a dollar is 6.67 lamports
+        assert_eq!(convert_amount(1, &usdc, 6, &sol, 9).unwrap(), 6);
+    }
+}
@@ -0,0 +1,96 @@
+use crate::{
+    constants::LOAN_PAGE_SIZE,
+    errors::AgioError,
+    state::{LoanPage, PortfolioRole, UserProfile},
+};
+use anchor_lang::prelude::*;
+
+//  page a 1-based slot lives in, 0 maps to page 0 and is never looked up
+pub fn page_of_slot(slot: u64) -> u64 {
+    slot.saturating_sub(1) / LOAN_PAGE_SIZE as u64
+}
+
+//  lists `loan` in the next free slot of `role`'s pages, returns the 1-based slot to store on the loan
+//  `page` must be the account at the side's next_page_index, the seeds in each instruction pin it
+pub fn list(
+    role: PortfolioRole,
+    loan: Pubkey,
+    wallet: Pubkey,
+    profile: &mut UserProfile,
+    page: &mut LoanPage,
+) -> Result<u64> {
+    profile.wallet = wallet;
+    let side = profile.side_mut(role);
+
+    let slot = side.next_slot;
+    page.wallet = wallet;
+    page.index = side.next_page_index();
+    page.loans[(slot % LOAN_PAGE_SIZE as u64) as usize] = loan;
+    page.live = page.live
+        .checked_add(1)
+        .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+
+    side.next_slot = slot
+        .checked_add(1)
+        .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+    side.active_count = side.active_count
+        .checked_add(1)
+        .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+
+    Ok(side.next_slot)
+}
+
+//  counts a borrowed loan towards `role`'s lifetime totals
+pub fn record(role: PortfolioRole, principal: u64, profile: &mut UserProfile) -> Result<()> {
+    let side = profile.side_mut(role);
+    side.lifetime_count = side.lifetime_count
+        .checked_add(1)
+        .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+    side.lifetime_volume = side.lifetime_volume
+        .checked_add(principal)
+        .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+    Ok(())
+}
+
+//  points `slot` of a page at `to` instead of `from`, a filled request keeps its borrower
+//  slot as it moves to the loan's address
+pub fn relist(from: Pubkey, to: Pubkey, slot: u64, page: &mut LoanPage) -> Result<()> {
+    let position = (slot.checked_sub(1).ok_or(AgioError::PortfolioMismatch)? % LOAN_PAGE_SIZE as u64) as usize;
+    require!(page.loans[position] == from, AgioError::PortfolioMismatch);
+    page.loans[position] = to;
+    Ok(())
+}
+
+//  clears `loan` from `slot` of `role`'s pages, closing the page to `wallet` once it is
+//  empty and no later loan can land in it
+//  loans listed before portfolios existed carry slot 0 and need neither account
+pub fn unlist<'info>(
+    role: PortfolioRole,
+    loan: Pubkey,
+    slot: u64,
+    profile: Option<&mut Box<Account<'info, UserProfile>>>,
+    page: Option<&mut Box<Account<'info, LoanPage>>>,
+    wallet: &AccountInfo<'info>,
+) -> Result<()> {
+    if slot == 0 {
+        return Ok(());
+    }
+    let profile = profile.ok_or(AgioError::PortfolioRequired)?;
+    let page = page.ok_or(AgioError::PortfolioRequired)?;
+    let side = profile.side_mut(role);
+
+    let position = ((slot - 1) % LOAN_PAGE_SIZE as u64) as usize;
+    require!(page.loans[position] == loan, AgioError::PortfolioMismatch);
+    page.loans[position] = Pubkey::default();
+    page.live = page.live
+        .checked_sub(1)
+        .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+    side.active_count = side.active_count
+        .checked_sub(1)
+        .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+
+    if page.live == 0 && page.index < side.next_page_index() {
+        page.close(wallet.clone())?;
+    }
+    Ok(())
+}
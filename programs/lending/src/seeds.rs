@@ -0,0 +1,67 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::LOAN;
+
+//  canonical loan pda layout: [LOAN, lender, loan_key]
+//  anchor's `seeds = [..]` only takes an inline list, so every
+//  #[derive(Accounts)] struct spells these same seeds in this order
+//  a borrower's request lives at [LOAN, borrower, loan_key] until fill_request
+//  moves it to the canonical address of its lender
+pub fn loan_seeds<'a>(lender: &'a Pubkey, loan_key: &'a Pubkey) -> [&'a [u8]; 3] {
+    [LOAN.as_bytes(), lender.as_ref(), loan_key.as_ref()]
+}
+
+//  seeds for signing as the loan pda
+pub fn loan_signer_seeds<'a>(
+    lender: &'a Pubkey,
+    loan_key: &'a Pubkey,
+    bump: &'a [u8; 1],
+) -> [&'a [u8]; 4] {
+    [LOAN.as_bytes(), lender.as_ref(), loan_key.as_ref(), bump]
+}
+
+//  seeds for signing as a loan of the original program, which derived it from
+//  [LOAN, loan_key] alone, only migrate_loan signs with these
+pub fn legacy_loan_signer_seeds<'a>(loan_key: &'a Pubkey, bump: &'a [u8; 1]) -> [&'a [u8]; 3] {
+    [LOAN.as_bytes(), loan_key.as_ref(), bump]
+}
+
+pub fn loan_address(lender: &Pubkey, loan_key: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&loan_seeds(lender, loan_key), &crate::ID)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loan_address_matches_the_account_seeds() {
+        let lender = Pubkey::new_unique();
+        let loan_key = Pubkey::new_unique();
+        let (address, bump) = loan_address(&lender, &loan_key);
+
+        //  as spelled inline by every #[derive(Accounts)] struct
+        let inline = Pubkey::create_program_address(
+            &[LOAN.as_bytes(), &lender.to_bytes(), &loan_key.to_bytes(), &[bump]],
+            &crate::ID,
+        )
+        .unwrap();
+        assert_eq!(address, inline);
+
+        let bump = [bump];
+        let signer = Pubkey::create_program_address(&loan_signer_seeds(&lender, &loan_key, &bump), &crate::ID).unwrap();
+        assert_eq!(address, signer);
+    }
+
+    #[test]
+    fn legacy_loan_address_differs_from_the_canonical_one() {
+        let lender = Pubkey::new_unique();
+        let loan_key = Pubkey::new_unique();
+        let (legacy, bump) = Pubkey::find_program_address(&[LOAN.as_bytes(), &loan_key.to_bytes()], &crate::ID);
+
+        let bump = [bump];
+        let signer = Pubkey::create_program_address(&legacy_loan_signer_seeds(&loan_key, &bump), &crate::ID).unwrap();
+        assert_eq!(legacy, signer);
+        assert_ne!(legacy, loan_address(&lender, &loan_key).0);
+    }
+}
@@ -1,7 +1,13 @@
 use anchor_lang::prelude::*;
 
+use crate::{constants::{BPS_DENOMINATOR, LOAN_PAGE_SIZE, PAUSE_ALL}, errors::AgioError, interest, portfolio};
+
 #[account]
+#[derive(InitSpace)]
 pub struct Config {
+    //  layout version, Config::VERSION once written or migrated by this program
+    pub version: u8,
+
     pub authority: Pubkey,
 
     pub pending_authority: Pubkey,
@@ -10,12 +16,61 @@ pub struct Config {
 
     pub lend_fee: u64,
     pub borrow_fee: u64,
+    pub liquidation_fee: u64,
+    //  borrow_fee is paid by the borrower on top of the principal when set,
+    //  otherwise it is withheld from the disbursed principal
+    pub borrow_fee_on_top: bool,
+    //  upper bound for every fee above, itself capped at BPS_DENOMINATOR
+    pub max_fee: u64,
 
     pub expire_duration: u8,
+
+    //  loan value over collateral value above which an active loan may be liquidated,
+    //  used for loans that do not set their own, 0 disables the fallback
+    pub liquidation_threshold_bps: u64,
+    //  extra collateral a liquidator receives on top of the debt it repays
+    pub liquidation_bonus_bps: u64,
+
+    //  may set pause_flags alongside the authority, default when unset
+    pub guardian: Pubkey,
+    //  PAUSE_* bits currently in force
+    pub pause_flags: u8,
+
+    //  room for new fields without another realloc, always zeroed
+    pub reserved: [u8; 64],
+}
+
+impl Config {
+    pub const VERSION: u8 = 1;
+
+    pub fn validate(&self) -> Result<()> {
+        require!(self.team_wallet != Pubkey::default(), AgioError::IncorrectTeamWallet);
+        require!(self.max_fee <= BPS_DENOMINATOR, AgioError::FeeTooHigh);
+        require!(self.lend_fee <= self.max_fee, AgioError::FeeTooHigh);
+        require!(self.borrow_fee <= self.max_fee, AgioError::FeeTooHigh);
+        require!(self.liquidation_fee <= self.max_fee, AgioError::FeeTooHigh);
+        require!(self.expire_duration > 0, AgioError::InvalidExpireDuration);
+        require!(self.liquidation_threshold_bps <= BPS_DENOMINATOR, AgioError::InvalidLiquidationThreshold);
+        require!(self.liquidation_bonus_bps <= BPS_DENOMINATOR, AgioError::InvalidLiquidationBonus);
+        require!(self.pause_flags & !PAUSE_ALL == 0, AgioError::InvalidPauseFlags);
+        Ok(())
+    }
+
+    pub fn is_paused(&self, flag: u8) -> bool {
+        self.pause_flags & flag != 0
+    }
+
+    pub fn can_pause(&self, key: &Pubkey) -> bool {
+        *key == self.authority || (self.guardian != Pubkey::default() && *key == self.guardian)
+    }
 }
 
 #[account]
+#[derive(InitSpace)]
 pub struct Loan{
+    //  layout version, Loan::VERSION once written or migrated by this program
+    pub version: u8,
+
     pub lender: Pubkey,
     pub borrower: Pubkey,
     
@@ -25,9 +80,472 @@ pub struct Loan{
     pub duration: i64,
     pub collateral_mint: Pubkey,
     pub collateral_amount: u64,
+    //  verified metaplex collection any of whose nfts may be posted, default for a single collateral_mint
+    pub collateral_collection: Pubkey,
+    //  seconds the borrower may push maturity back without the lender's signature
+    pub max_extension: i64,
+
+    pub price_terms: PriceTerms,
 
     pub create_date: i64,
     pub expire_date: i64,
     pub borrow_date: i64,
-    pub repaid: bool,
-}
\ No newline at end of file
+    pub status: LoanStatus,
+    //  bumped by every update_offer, borrow_loan must name the version it agreed to
+    pub offer_version: u64,
+
+    //  principal still owed, interest accrues on this from last_payment_ts
+    pub principal_outstanding: u64,
+    pub interest_paid: u64,
+    pub last_payment_ts: i64,
+
+    //  1-based slots of this loan in the lender's and borrower's portfolio pages,
+    //  0 for loans created before portfolios were indexed
+    pub lender_slot: u64,
+    pub borrower_slot: u64,
+
+    //  room for new fields without another realloc, always zeroed
+    pub reserved: [u8; 48],
+}
+
+//  per-wallet portfolio at [PROFILE, wallet], indexes the wallet's open loans on each side
+#[account]
+#[derive(InitSpace)]
+pub struct UserProfile {
+    pub wallet: Pubkey,
+
+    pub as_lender: PortfolioSide,
+    pub as_borrower: PortfolioSide,
+    //  borrowed loans that ended in claim_collateral
+    pub default_count: u32,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct PortfolioSide {
+    //  loans currently listed in this side's pages, offers included on the lender side
+    pub active_count: u32,
+    //  slots handed out so far, the next loan is listed at slot next_slot + 1
+    pub next_slot: u64,
+    //  loans borrowed on this side over the wallet's lifetime
+    pub lifetime_count: u64,
+    //  principal of those loans in raw token units, summed across mints
+    pub lifetime_volume: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PortfolioRole {
+    Lender,
+    Borrower,
+}
+
+impl UserProfile {
+    pub fn side_mut(&mut self, role: PortfolioRole) -> &mut PortfolioSide {
+        match role {
+            PortfolioRole::Lender => &mut self.as_lender,
+            PortfolioRole::Borrower => &mut self.as_borrower,
+        }
+    }
+}
+
+impl PortfolioSide {
+    //  page the next listed loan lands in
+    pub fn next_page_index(&self) -> u64 {
+        self.next_slot / LOAN_PAGE_SIZE as u64
+    }
+}
+
+//  one page of a wallet's loan keys at [LENDER_PAGE | BORROWER_PAGE, wallet, index],
+//  closed loans leave a default key behind
+#[account]
+#[derive(InitSpace)]
+pub struct LoanPage {
+    pub wallet: Pubkey,
+    pub index: u64,
+    //  non-default keys in loans
+    pub live: u32,
+    pub loans: [Pubkey; LOAN_PAGE_SIZE],
+}
+
+//  per-mint registry entry at [ASSET, mint], a mint without one cannot be lent or posted
+#[account]
+#[derive(InitSpace)]
+pub struct AssetConfig {
+    pub mint: Pubkey,
+
+    pub allowed_as_loan: bool,
+    pub allowed_as_collateral: bool,
+    //  bounds on loan_amount when lent, a max of 0 leaves it uncapped
+    pub min_loan_amount: u64,
+    pub max_loan_amount: u64,
+    //  replaces Config::borrow_fee for loans of this mint when set
+    pub borrow_fee: Option<u64>,
+    //  pyth feed a priced loan must use for this mint, zeroed when any feed is accepted
+    pub price_feed: [u8; 32],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct AssetParams {
+    pub allowed_as_loan: bool,
+    pub allowed_as_collateral: bool,
+    pub min_loan_amount: u64,
+    pub max_loan_amount: u64,
+    pub borrow_fee: Option<u64>,
+    pub price_feed: [u8; 32],
+}
+
+impl AssetConfig {
+    pub fn validate(&self, global_config: &Config) -> Result<()> {
+        require!(
+            self.max_loan_amount == 0 || self.min_loan_amount <= self.max_loan_amount,
+            AgioError::InvalidLoanAmountRange
+        );
+        if let Some(borrow_fee) = self.borrow_fee {
+            require!(borrow_fee <= global_config.max_fee, AgioError::FeeTooHigh);
+        }
+        Ok(())
+    }
+
+    pub fn require_loan(&self, loan_amount: u64) -> Result<()> {
+        require!(self.allowed_as_loan, AgioError::AssetNotAllowed);
+        require!(loan_amount >= self.min_loan_amount, AgioError::LoanAmountOutOfRange);
+        require!(
+            self.max_loan_amount == 0 || loan_amount <= self.max_loan_amount,
+            AgioError::LoanAmountOutOfRange
+        );
+        Ok(())
+    }
+
+    pub fn require_collateral(&self) -> Result<()> {
+        require!(self.allowed_as_collateral, AgioError::AssetNotAllowed);
+        Ok(())
+    }
+
+    pub fn require_feed(&self, feed_id: &[u8; 32]) -> Result<()> {
+        require!(self.price_feed == [0; 32] || self.price_feed == *feed_id, AgioError::InvalidPriceFeed);
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct PriceTerms {
+    //  minimum collateral value over loan value at borrow time, 0 disables the check
+    pub min_collateral_ratio_bps: u64,
+    //  pyth feed ids the ratio is priced with
+    pub loan_price_feed: [u8; 32],
+    pub collateral_price_feed: [u8; 32],
+    //  overrides Config::liquidation_threshold_bps when set
+    pub liquidation_threshold_bps: u64,
+}
+
+impl PriceTerms {
+    pub fn is_priced(&self) -> bool {
+        self.min_collateral_ratio_bps > 0
+    }
+
+    pub fn has_feeds(&self) -> bool {
+        self.loan_price_feed != [0; 32] && self.collateral_price_feed != [0; 32]
+    }
+
+    //  a priced offer must ask for at least full collateralisation and name both feeds
+    pub fn validate(&self) -> Result<()> {
+        if self.is_priced() {
+            require!(self.min_collateral_ratio_bps >= BPS_DENOMINATOR, AgioError::InvalidCollateralRatio);
+            require!(self.has_feeds(), AgioError::InvalidPriceFeed);
+        }
+        if self.liquidation_threshold_bps > 0 {
+            require!(self.liquidation_threshold_bps <= BPS_DENOMINATOR, AgioError::InvalidLiquidationThreshold);
+            require!(self.has_feeds(), AgioError::InvalidPriceFeed);
+        }
+        Ok(())
+    }
+
+    pub fn liquidation_threshold_bps(&self, global_config: &Config) -> u64 {
+        if self.liquidation_threshold_bps > 0 {
+            self.liquidation_threshold_bps
+        } else {
+            global_config.liquidation_threshold_bps
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LoanStatus {
+    Offered,
+    Requested,
+    Active,
+    Repaid,
+    Defaulted,
+    Cancelled,
+    Expired,
+    Liquidated,
+}
+
+impl LoanStatus {
+    //  the only legal moves are
+    //  Offered -> Active | Cancelled | Expired
+    //  Requested -> Active | Cancelled | Expired
+    //  Active  -> Repaid | Defaulted | Liquidated
+    pub fn transition(self, next: LoanStatus) -> Result<LoanStatus> {
+        use LoanStatus::*;
+
+        match (self, next) {
+            (Offered | Requested, Active | Cancelled | Expired) => Ok(next),
+            (Active, Repaid | Defaulted | Liquidated) => Ok(next),
+
+            (Offered | Requested, Repaid | Defaulted | Liquidated) => err!(AgioError::LoanNotBorrowed),
+            (Active, Active) => err!(AgioError::AlreadyLended),
+            (Active, Cancelled | Expired) => err!(AgioError::LoanActivated),
+            (Repaid, _) => err!(AgioError::AlreadyRepaid),
+            (Defaulted, _) => err!(AgioError::AlreadyDefaulted),
+            (Cancelled, _) => err!(AgioError::AlreadyCancelled),
+            (Expired, _) => err!(AgioError::AlreadyExpired),
+            (Liquidated, _) => err!(AgioError::AlreadyLiquidated),
+            (_, Offered | Requested) => err!(AgioError::InvalidStatusTransition),
+        }
+    }
+
+    //  fails with the same error a move out of Active would
+    pub fn require_active(self) -> Result<()> {
+        self.transition(LoanStatus::Repaid).map(|_| ())
+    }
+}
+
+impl Loan {
+    pub const VERSION: u8 = 1;
+
+    pub fn is_collection_offer(&self) -> bool {
+        self.collateral_collection != Pubkey::default()
+    }
+
+    pub fn lender_page_index(&self) -> u64 {
+        portfolio::page_of_slot(self.lender_slot)
+    }
+
+    pub fn borrower_page_index(&self) -> u64 {
+        portfolio::page_of_slot(self.borrower_slot)
+    }
+
+    //  page a request is listed on, or the borrower's next page for a request made
+    //  before requests were listed
+    pub fn request_page_index(&self, borrower: &PortfolioSide) -> u64 {
+        if self.borrower_slot == 0 {
+            borrower.next_page_index()
+        } else {
+            self.borrower_page_index()
+        }
+    }
+
+    //  mint the collateral's AssetConfig is keyed by, the collection for a collection offer
+    pub fn collateral_asset_mint(&self) -> Pubkey {
+        if self.is_collection_offer() {
+            self.collateral_collection
+        } else {
+            self.collateral_mint
+        }
+    }
+
+    //  an nft of the collection on a collection offer, the offered mint otherwise
+    pub fn accepts_collateral_mint(&self, mint: &Pubkey) -> bool {
+        self.is_collection_offer() || *mint == self.collateral_mint
+    }
+
+    //  `received` is what reached the vault, net of any transfer fee
+    pub fn require_collateral(&self, received: u64) -> Result<()> {
+        require!(received >= self.collateral_amount, AgioError::InsufficientCollateral);
+        Ok(())
+    }
+
+    pub fn transition(&mut self, next: LoanStatus) -> Result<()> {
+        self.status = self.status.transition(next)?;
+        Ok(())
+    }
+
+    pub fn maturity(&self) -> Result<i64> {
+        self.borrow_date
+            .checked_add(self.duration)
+            .ok_or(error!(AgioError::OverflowOrUnderflowOccurred))
+    }
+
+    //  seconds of unpaid accrual since last_payment_ts, capped at maturity
+    pub fn accrual_seconds(&self, now: i64) -> Result<i64> {
+        let remaining = self.maturity()?
+            .checked_sub(self.last_payment_ts)
+            .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
+        interest::accrual_seconds(self.last_payment_ts, remaining, now)
+    }
+
+    //  interest accrued on principal_outstanding and not yet paid
+    pub fn interest_owed(&self, now: i64) -> Result<u64> {
+        interest::accrued_interest(self.principal_outstanding, self.interest_rate, self.accrual_seconds(now)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offer(collateral_mint: Pubkey, collateral_amount: u64, collateral_collection: Pubkey) -> Loan {
+        Loan {
+            version: Loan::VERSION,
+            lender: Pubkey::new_unique(),
+            borrower: Pubkey::default(),
+            loan_mint: Pubkey::new_unique(),
+            loan_amount: 1_000,
+            interest_rate: 1_000,
+            duration: 30 * 24 * 60 * 60,
+            collateral_mint,
+            collateral_amount,
+            collateral_collection,
+            max_extension: 0,
+            price_terms: PriceTerms::default(),
+            create_date: 0,
+            expire_date: 7 * 24 * 60 * 60,
+            borrow_date: 0,
+            status: LoanStatus::Offered,
+            offer_version: 0,
+            principal_outstanding: 0,
+            interest_paid: 0,
+            last_payment_ts: 0,
+            lender_slot: 1,
+            borrower_slot: 0,
+            reserved: [0; 48],
+        }
+    }
+
+    #[test]
+    fn loan_status_transition_table() {
+        use AgioError::*;
+        use LoanStatus::*;
+
+        const ALL: [LoanStatus; 8] = [Offered, Requested, Active, Repaid, Defaulted, Cancelled, Expired, Liquidated];
+
+        //  one row per current status, one column per entry of ALL, None where the move is legal
+        let table: [(LoanStatus, [Option<AgioError>; 8]); 8] = [
+            (Offered, [
+                Some(InvalidStatusTransition), Some(InvalidStatusTransition), None, Some(LoanNotBorrowed),
+                Some(LoanNotBorrowed), None, None, Some(LoanNotBorrowed),
+            ]),
+            (Requested, [
+                Some(InvalidStatusTransition), Some(InvalidStatusTransition), None, Some(LoanNotBorrowed),
+                Some(LoanNotBorrowed), None, None, Some(LoanNotBorrowed),
+            ]),
+            (Active, [
+                Some(InvalidStatusTransition), Some(InvalidStatusTransition), Some(AlreadyLended), None,
+                None, Some(LoanActivated), Some(LoanActivated), None,
+            ]),
+            (Repaid, [Some(AlreadyRepaid); 8]),
+            (Defaulted, [Some(AlreadyDefaulted); 8]),
+            (Cancelled, [Some(AlreadyCancelled); 8]),
+            (Expired, [Some(AlreadyExpired); 8]),
+            (Liquidated, [Some(AlreadyLiquidated); 8]),
+        ];
+
+        for (from, row) in table {
+            for (next, expected) in ALL.into_iter().zip(row) {
+                let result = from.transition(next);
+                match expected {
+                    None => assert_eq!(result.ok(), Some(next), "{from:?} -> {next:?}"),
+                    Some(error) => assert_eq!(result.err(), Some(error.into()), "{from:?} -> {next:?}"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn require_active_only_passes_for_active() {
+        assert!(LoanStatus::Active.require_active().is_ok());
+        assert_eq!(LoanStatus::Offered.require_active().err(), Some(AgioError::LoanNotBorrowed.into()));
+        assert_eq!(LoanStatus::Repaid.require_active().err(), Some(AgioError::AlreadyRepaid.into()));
+    }
+
+    #[test]
+    fn offer_accepts_only_its_collateral_mint() {
+        let mint = Pubkey::new_unique();
+        let loan = offer(mint, 5, Pubkey::default());
+        assert!(loan.accepts_collateral_mint(&mint));
+        assert!(!loan.accepts_collateral_mint(&Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn collection_offer_leaves_the_mint_to_the_metadata_check() {
+        let collection = Pubkey::new_unique();
+        let loan = offer(collection, 1, collection);
+        assert!(loan.accepts_collateral_mint(&Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn under_collateralised_borrow_is_refused() {
+        let loan = offer(Pubkey::new_unique(), 5, Pubkey::default());
+        assert!(loan.require_collateral(5).is_ok());
+        assert!(loan.require_collateral(6).is_ok());
+        assert_eq!(loan.require_collateral(4).unwrap_err(), AgioError::InsufficientCollateral.into());
+        assert_eq!(loan.require_collateral(0).unwrap_err(), AgioError::InsufficientCollateral.into());
+    }
+
+    #[test]
+    fn request_page_follows_its_borrower_slot() {
+        let mut request = offer(Pubkey::new_unique(), 5, Pubkey::default());
+        let side = PortfolioSide { active_count: 40, next_slot: 40, lifetime_count: 0, lifetime_volume: 0 };
+        //  a request made before requests were listed lands on the borrower's next page
+        assert_eq!(request.request_page_index(&side), 1);
+        request.borrower_slot = 3;
+        assert_eq!(request.request_page_index(&side), 0);
+        request.borrower_slot = 33;
+        assert_eq!(request.request_page_index(&side), 1);
+    }
+
+    //  serialized length, discriminator included, of a fully populated account
+    fn written_len<T: AccountSerialize>(account: &T) -> usize {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        data.len()
+    }
+
+    #[test]
+    fn init_space_matches_the_serialized_size() {
+        let side = PortfolioSide { active_count: u32::MAX, next_slot: u64::MAX, lifetime_count: u64::MAX, lifetime_volume: u64::MAX };
+
+        let config = Config {
+            version: Config::VERSION,
+            authority: Pubkey::new_unique(),
+            pending_authority: Pubkey::new_unique(),
+            team_wallet: Pubkey::new_unique(),
+            lend_fee: u64::MAX,
+            borrow_fee: u64::MAX,
+            liquidation_fee: u64::MAX,
+            borrow_fee_on_top: true,
+            max_fee: u64::MAX,
+            expire_duration: u8::MAX,
+            liquidation_threshold_bps: u64::MAX,
+            liquidation_bonus_bps: u64::MAX,
+            guardian: Pubkey::new_unique(),
+            pause_flags: PAUSE_ALL,
+            reserved: [u8::MAX; 64],
+        };
+        let mut loan = offer(Pubkey::new_unique(), u64::MAX, Pubkey::new_unique());
+        loan.price_terms = PriceTerms {
+            min_collateral_ratio_bps: u64::MAX,
+            loan_price_feed: [1; 32],
+            collateral_price_feed: [2; 32],
+            liquidation_threshold_bps: u64::MAX,
+        };
+        loan.status = LoanStatus::Liquidated;
+        let asset = AssetConfig {
+            mint: Pubkey::new_unique(),
+            allowed_as_loan: true,
+            allowed_as_collateral: true,
+            min_loan_amount: u64::MAX,
+            max_loan_amount: u64::MAX,
+            borrow_fee: Some(u64::MAX),
+            price_feed: [3; 32],
+        };
+        let profile = UserProfile { wallet: Pubkey::new_unique(), as_lender: side, as_borrower: side, default_count: u32::MAX };
+        let page = LoanPage { wallet: Pubkey::new_unique(), index: u64::MAX, live: u32::MAX, loans: [Pubkey::new_unique(); LOAN_PAGE_SIZE] };
+
+        assert_eq!(written_len(&config), 8 + Config::INIT_SPACE);
+        assert_eq!(written_len(&loan), 8 + Loan::INIT_SPACE);
+        assert_eq!(written_len(&asset), 8 + AssetConfig::INIT_SPACE);
+        assert_eq!(written_len(&profile), 8 + UserProfile::INIT_SPACE);
+        assert_eq!(written_len(&page), 8 + LoanPage::INIT_SPACE);
+    }
+}
@@ -1,5 +1,18 @@
-use crate::*;
-use anchor_spl::token::{self, Token};
+use crate::{errors::AgioError, *};
+use anchor_spl::{
+    metadata::{mpl_token_metadata::types::TokenStandard, MetadataAccount},
+    token_2022_extensions::{harvest_withheld_tokens_to_mint, HarvestWithheldTokensToMint},
+    token_2022::spl_token_2022::{
+        self,
+        extension::{
+            default_account_state::DefaultAccountState,
+            transfer_fee::{TransferFeeAmount, TransferFeeConfig},
+            BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+        },
+        state::AccountState,
+    },
+    token_interface::{self, Mint, TokenAccount, TokenInterface},
+};
 use solana_program::program::{invoke, invoke_signed};
 use std::ops::{Div, Mul};
 
@@ -52,18 +65,20 @@ pub fn token_transfer_user<'info>(
     from: AccountInfo<'info>,
     authority: &Signer<'info>,
     to: AccountInfo<'info>,
-    token_program: &Program<'info, Token>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
     amount: u64,
 ) -> Result<()> {
     let cpi_ctx: CpiContext<_> = CpiContext::new(
         token_program.to_account_info(),
-        token::Transfer {
+        token_interface::TransferChecked {
             from,
-            authority: authority.to_account_info(),
+            mint: mint.to_account_info(),
             to,
+            authority: authority.to_account_info(),
         },
     );
-    token::transfer(cpi_ctx, amount)?;
+    token_interface::transfer_checked(cpi_ctx, amount, mint.decimals)?;
 
     Ok(())
 }
@@ -73,20 +88,238 @@ pub fn token_transfer_with_signer<'info>(
     from: AccountInfo<'info>,
     authority: AccountInfo<'info>,
     to: AccountInfo<'info>,
-    token_program: &Program<'info, Token>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
     signer_seeds: &[&[&[u8]]; 1],
     amount: u64,
 ) -> Result<()> {
     let cpi_ctx: CpiContext<_> = CpiContext::new_with_signer(
         token_program.to_account_info(),
-        token::Transfer {
+        token_interface::TransferChecked {
             from,
+            mint: mint.to_account_info(),
             to,
             authority,
         },
         signer_seeds,
     );
-    token::transfer(cpi_ctx, amount)?;
+    token_interface::transfer_checked(cpi_ctx, amount, mint.decimals)?;
+
+    Ok(())
+}
+
+//  close token account owned by PDA, rent goes to destination
+pub fn token_close_with_signer<'info>(
+    account: AccountInfo<'info>,
+    destination: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+    signer_seeds: &[&[&[u8]]; 1],
+) -> Result<()> {
+    harvest_withheld_fees(&account, mint, token_program)?;
+
+    let cpi_ctx: CpiContext<_> = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        token_interface::CloseAccount {
+            account,
+            destination,
+            authority,
+        },
+        signer_seeds,
+    );
+    token_interface::close_account(cpi_ctx)?;
+
+    Ok(())
+}
+
+//  close token account owned by PDA once it holds no tokens, a vault that
+//  was already closed or still holds a balance is left untouched
+pub fn token_close_if_empty_with_signer<'info>(
+    account: AccountInfo<'info>,
+    destination: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+    signer_seeds: &[&[&[u8]]; 1],
+) -> Result<()> {
+    if account.data_is_empty() {
+        return Ok(());
+    }
+    let amount = TokenAccount::try_deserialize(&mut &account.try_borrow_data()?[..])?.amount;
+    if amount != 0 {
+        return Ok(());
+    }
+    token_close_with_signer(account, destination, authority, mint, token_program, signer_seeds)
+}
+
+//  every deposit of a transfer-fee mint leaves the fee withheld on the receiving vault,
+//  and token-2022 refuses to close it until that is swept to the mint, which anyone may do
+//  the mint must be writable whenever such a vault can be closed
+fn harvest_withheld_fees<'info>(
+    account: &AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<()> {
+    if *account.owner != spl_token_2022::ID {
+        return Ok(());
+    }
+
+    let withheld_amount = {
+        let data = account.try_borrow_data()?;
+        let state = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?;
+        state
+            .get_extension::<TransferFeeAmount>()
+            .map(|fee| u64::from(fee.withheld_amount))
+            .unwrap_or(0)
+    };
+    if withheld_amount == 0 {
+        return Ok(());
+    }
+
+    harvest_withheld_tokens_to_mint(
+        CpiContext::new(
+            token_program.to_account_info(),
+            HarvestWithheldTokensToMint {
+                token_program_id: token_program.to_account_info(),
+                mint: mint.to_account_info(),
+            },
+        ),
+        vec![account.clone()],
+    )
+}
+
+//  token-2022 extensions the protocol cannot custody safely
+//  transfer hooks are refused too, transfers here never carry the hook's extra accounts
+pub fn validate_mint(mint: &InterfaceAccount<'_, Mint>) -> Result<()> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner != spl_token_2022::ID {
+        return Ok(());
+    }
+
+    let data = mint_info.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    for extension in state.get_extension_types()? {
+        match extension {
+            ExtensionType::PermanentDelegate
+            | ExtensionType::NonTransferable
+            | ExtensionType::TransferHook => {
+                return err!(AgioError::UnsupportedMintExtension);
+            }
+            ExtensionType::DefaultAccountState => {
+                let default_state = state.get_extension::<DefaultAccountState>()?;
+                if default_state.state == AccountState::Frozen as u8 {
+                    return err!(AgioError::UnsupportedMintExtension);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+//  a single nft whose metadata carries a verified membership of `collection`
+//  programmable nfts are refused, they only move through the token metadata program
+pub fn validate_collection_nft(
+    mint: &InterfaceAccount<'_, Mint>,
+    metadata: &MetadataAccount,
+    collection: &Pubkey,
+) -> Result<()> {
+    require!(mint.supply == 1 && mint.decimals == 0, AgioError::InvalidCollectionNft);
+    require!(metadata.mint == mint.key(), AgioError::InvalidCollectionNft);
+    require!(
+        !matches!(
+            metadata.token_standard,
+            Some(TokenStandard::ProgrammableNonFungible | TokenStandard::ProgrammableNonFungibleEdition)
+        ),
+        AgioError::InvalidCollectionNft
+    );
+
+    match &metadata.collection {
+        Some(member) if member.verified && member.key == *collection => Ok(()),
+        _ => err!(AgioError::NotInCollection),
+    }
+}
+
+//  amount that lands in the destination once the mint's transfer fee is withheld
+pub fn received_amount(mint: &InterfaceAccount<'_, Mint>, amount: u64) -> Result<u64> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner != spl_token_2022::ID {
+        return Ok(amount);
+    }
+
+    let data = mint_info.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    let fee = match state.get_extension::<TransferFeeConfig>() {
+        Ok(transfer_fee_config) => transfer_fee_config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(AgioError::OverflowOrUnderflowOccurred)?,
+        Err(_) => 0,
+    };
+
+    amount
+        .checked_sub(fee)
+        .ok_or(error!(AgioError::OverflowOrUnderflowOccurred))
+}
+
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    *mint == spl_token::native_mint::ID
+}
+
+//  wrap sol from user into a wsol token account
+pub fn wrap_sol_from_user<'info>(
+    signer: &Signer<'info>,
+    token_account: AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<()> {
+    sol_transfer_from_user(signer, token_account.clone(), system_program, amount)?;
+    sync_native(token_account, token_program)
+}
+
+//  wrap sol held by an account owned by this program into a wsol token account
+pub fn wrap_sol_from_program_account<'info>(
+    source: &AccountInfo<'info>,
+    token_account: AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<()> {
+    sol_transfer_from_program_account(source, &token_account, amount)?;
+    sync_native(token_account, token_program)
+}
+
+fn sync_native<'info>(
+    token_account: AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<()> {
+    let cpi_ctx: CpiContext<_> = CpiContext::new(
+        token_program.to_account_info(),
+        token_interface::SyncNative {
+            account: token_account,
+        },
+    );
+    token_interface::sync_native(cpi_ctx)?;
+
+    Ok(())
+}
+
+//  transfer sol out of an account owned by this program
+pub fn sol_transfer_from_program_account<'info>(
+    source: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let source_lamports = source
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(AgioError::InsufficientFunds)?;
+    let destination_lamports = destination
+        .lamports()
+        .checked_add(amount)
+        .ok_or(AgioError::OverflowOrUnderflowOccurred)?;
 
+    **source.try_borrow_mut_lamports()? = source_lamports;
+    **destination.try_borrow_mut_lamports()? = destination_lamports;
     Ok(())
 }
@@ -0,0 +1,73 @@
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  allowAsset,
+  balance,
+  borrowMethod,
+  createOffer,
+  ensureConfig,
+  expectError,
+  fundedWallet,
+  MAX_FEE,
+  mintTokens,
+  newMint,
+  program,
+  setMaxFee,
+  teamWallet,
+} from "./setup";
+
+const LOAN_AMOUNT = 10_000_000;
+const COLLATERAL_AMOUNT = 1;
+
+//  the borrow fee is set per loan mint through its AssetConfig override
+describe("borrow_loan fee edges", () => {
+  let lender: Keypair;
+  let borrower: Keypair;
+  let loanMint: PublicKey;
+  let collateralMint: PublicKey;
+
+  before(async () => {
+    await ensureConfig();
+
+    loanMint = await newMint(6);
+    collateralMint = await newMint(0);
+    await allowAsset(collateralMint);
+
+    lender = await fundedWallet();
+    borrower = await fundedWallet();
+    await mintTokens(loanMint, lender.publicKey, 2 * LOAN_AMOUNT);
+    await mintTokens(collateralMint, borrower.publicKey, 2 * COLLATERAL_AMOUNT);
+  });
+
+  after(async () => {
+    await allowAsset(loanMint);
+    await setMaxFee(MAX_FEE);
+  });
+
+  it("disburses the whole principal with a zero fee", async () => {
+    await allowAsset(loanMint, new BN(0));
+    const offer = await createOffer(lender, loanMint, collateralMint, LOAN_AMOUNT, COLLATERAL_AMOUNT);
+
+    await (await borrowMethod(offer, borrower, COLLATERAL_AMOUNT)).rpc();
+
+    expect(await balance(borrower.publicKey, loanMint)).to.equal(BigInt(LOAN_AMOUNT));
+    expect(await balance(teamWallet.publicKey, loanMint)).to.equal(BigInt(0));
+    const loan = await program.account.loan.fetch(offer.loan);
+    expect(loan.principalOutstanding.toNumber()).to.equal(LOAN_AMOUNT);
+  });
+
+  it("refuses a withheld fee of the whole principal", async () => {
+    await setMaxFee(10_000);
+    await allowAsset(loanMint, new BN(10_000));
+    const offer = await createOffer(lender, loanMint, collateralMint, LOAN_AMOUNT, COLLATERAL_AMOUNT);
+    const borrowerBefore = await balance(borrower.publicKey, loanMint);
+
+    await expectError((await borrowMethod(offer, borrower, COLLATERAL_AMOUNT)).rpc(), "NothingDisbursed");
+
+    const loan = await program.account.loan.fetch(offer.loan);
+    expect(loan.status).to.deep.equal({ offered: {} });
+    expect(await balance(borrower.publicKey, loanMint)).to.equal(borrowerBefore);
+    expect(await balance(offer.loan, loanMint)).to.equal(BigInt(LOAN_AMOUNT));
+  });
+});
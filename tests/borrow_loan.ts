@@ -0,0 +1,75 @@
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  allowAsset,
+  balance,
+  borrowMethod,
+  createOffer,
+  ensureConfig,
+  expectError,
+  fundedWallet,
+  mintTokens,
+  newMint,
+  Offer,
+  program,
+} from "./setup";
+
+const LOAN_AMOUNT = 50_000_000;
+const COLLATERAL_AMOUNT = 5;
+
+describe("borrow_loan collateral checks", () => {
+  let lender: Keypair;
+  let borrower: Keypair;
+  let loanMint: PublicKey;
+  let collateralMint: PublicKey;
+  let otherMint: PublicKey;
+  let offer: Offer;
+
+  before(async () => {
+    await ensureConfig();
+
+    loanMint = await newMint(6);
+    collateralMint = await newMint(0);
+    otherMint = await newMint(0);
+    await allowAsset(loanMint);
+    await allowAsset(collateralMint);
+    await allowAsset(otherMint);
+
+    lender = await fundedWallet();
+    borrower = await fundedWallet();
+    await mintTokens(loanMint, lender.publicKey, LOAN_AMOUNT);
+    await mintTokens(collateralMint, borrower.publicKey, 10);
+    await mintTokens(otherMint, borrower.publicKey, 10);
+
+    offer = await createOffer(lender, loanMint, collateralMint, LOAN_AMOUNT, COLLATERAL_AMOUNT);
+  });
+
+  it("refuses less collateral than the offer asks", async () => {
+    await expectError((await borrowMethod(offer, borrower, COLLATERAL_AMOUNT - 1)).rpc(), "InsufficientCollateral");
+    await expectError((await borrowMethod(offer, borrower, 0)).rpc(), "InsufficientCollateral");
+  });
+
+  it("refuses collateral of another mint", async () => {
+    await expectError(
+      (await borrowMethod(offer, borrower, COLLATERAL_AMOUNT, otherMint)).rpc(),
+      "InvalidCollateral"
+    );
+  });
+
+  it("leaves the offer untouched after a refused borrow", async () => {
+    const loan = await program.account.loan.fetch(offer.loan);
+    expect(loan.status).to.deep.equal({ offered: {} });
+    expect(await balance(borrower.publicKey, collateralMint)).to.equal(BigInt(10));
+    expect(await balance(borrower.publicKey, otherMint)).to.equal(BigInt(10));
+    expect(await balance(offer.loan, loanMint)).to.equal(BigInt(LOAN_AMOUNT));
+  });
+
+  it("accepts more collateral than the offer asks", async () => {
+    await (await borrowMethod(offer, borrower, COLLATERAL_AMOUNT + 2)).rpc();
+
+    const loan = await program.account.loan.fetch(offer.loan);
+    expect(loan.status).to.deep.equal({ active: {} });
+    expect(loan.collateralAmount.toNumber()).to.equal(COLLATERAL_AMOUNT + 2);
+    expect(await balance(offer.loan, collateralMint)).to.equal(BigInt(COLLATERAL_AMOUNT + 2));
+  });
+});
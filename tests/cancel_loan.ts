@@ -0,0 +1,49 @@
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  allowAsset,
+  ata,
+  balance,
+  cancelMethod,
+  connection,
+  createOffer,
+  ensureConfig,
+  fundedWallet,
+  mintTokens,
+  newMint,
+  program,
+} from "./setup";
+
+const LOAN_AMOUNT = 20_000_000;
+
+describe("cancel_loan", () => {
+  let lender: Keypair;
+  let loanMint: PublicKey;
+  let collateralMint: PublicKey;
+
+  before(async () => {
+    await ensureConfig();
+
+    loanMint = await newMint(6);
+    collateralMint = await newMint(0);
+    await allowAsset(loanMint);
+    await allowAsset(collateralMint);
+
+    lender = await fundedWallet();
+    await mintTokens(loanMint, lender.publicKey, LOAN_AMOUNT);
+  });
+
+  it("sweeps tokens sent to the collateral vault and closes it", async () => {
+    const offer = await createOffer(lender, loanMint, collateralMint, LOAN_AMOUNT, 5);
+    //  anyone can send tokens to the vault, which would otherwise keep it open
+    await mintTokens(collateralMint, offer.loan, 1);
+
+    await (await cancelMethod(offer)).rpc();
+
+    expect(await program.account.loan.fetchNullable(offer.loan)).to.equal(null);
+    expect(await connection.getAccountInfo(ata(offer.loan, collateralMint))).to.equal(null);
+    expect(await connection.getAccountInfo(ata(offer.loan, loanMint))).to.equal(null);
+    expect(await balance(lender.publicKey, collateralMint)).to.equal(BigInt(1));
+    expect(await balance(lender.publicKey, loanMint)).to.equal(BigInt(LOAN_AMOUNT));
+  });
+});
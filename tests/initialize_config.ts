@@ -0,0 +1,25 @@
+import { BN } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { admin, configPda, ensureConfig, expectError, fundedWallet, program, programData } from "./setup";
+
+describe("initialize_config", () => {
+  it("refuses a payer that is not the upgrade authority", async () => {
+    const intruder = await fundedWallet();
+
+    await expectError(
+      program.methods
+        .initializeConfig(intruder.publicKey, new BN(0), new BN(0), new BN(0), new BN(0), 1)
+        .accountsPartial({ payer: intruder.publicKey, programData })
+        .signers([intruder])
+        .rpc(),
+      "IncorrectAuthority"
+    );
+  });
+
+  it("hands the config to the upgrade authority", async () => {
+    await ensureConfig();
+
+    const config = await program.account.config.fetch(configPda);
+    expect(config.authority.toBase58()).to.equal(admin.publicKey.toBase58());
+  });
+});
@@ -0,0 +1,99 @@
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  allowAsset,
+  balance,
+  borrowMethod,
+  BORROW_FEE,
+  createOffer,
+  ensureConfig,
+  fundedWallet,
+  LEND_FEE,
+  mintTokens,
+  newMint,
+  profilePda,
+  program,
+  repayMethod,
+  teamWallet,
+} from "./setup";
+
+const LOAN_AMOUNT = 100_000_000;
+const COLLATERAL_AMOUNT = 5;
+
+describe("create -> borrow -> repay", () => {
+  let lender: Keypair;
+  let borrower: Keypair;
+  let loanMint: PublicKey;
+  let collateralMint: PublicKey;
+
+  before(async () => {
+    await ensureConfig();
+
+    loanMint = await newMint(6);
+    collateralMint = await newMint(0);
+    await allowAsset(loanMint);
+    await allowAsset(collateralMint);
+
+    lender = await fundedWallet();
+    borrower = await fundedWallet();
+    await mintTokens(loanMint, lender.publicKey, LOAN_AMOUNT);
+    await mintTokens(collateralMint, borrower.publicKey, 10);
+    //  enough for the interest on top of the returned principal
+    await mintTokens(loanMint, borrower.publicKey, 1_000_000);
+  });
+
+  it("runs a loan through its whole life", async () => {
+    //  create, the principal moves into the loan's vault
+    const offer = await createOffer(lender, loanMint, collateralMint, LOAN_AMOUNT, COLLATERAL_AMOUNT);
+
+    let loan = await program.account.loan.fetch(offer.loan);
+    expect(loan.status).to.deep.equal({ offered: {} });
+    expect(loan.lender.toBase58()).to.equal(lender.publicKey.toBase58());
+    expect(loan.loanAmount.toNumber()).to.equal(LOAN_AMOUNT);
+    expect(await balance(lender.publicKey, loanMint)).to.equal(BigInt(0));
+    expect(await balance(offer.loan, loanMint)).to.equal(BigInt(LOAN_AMOUNT));
+
+    //  borrow, the borrow fee is withheld from the disbursed principal
+    const borrowerLoanBefore = await balance(borrower.publicKey, loanMint);
+    await (await borrowMethod(offer, borrower, COLLATERAL_AMOUNT)).rpc();
+
+    const borrowFee = Math.ceil((LOAN_AMOUNT * BORROW_FEE) / 10_000);
+    loan = await program.account.loan.fetch(offer.loan);
+    expect(loan.status).to.deep.equal({ active: {} });
+    expect(loan.borrower.toBase58()).to.equal(borrower.publicKey.toBase58());
+    expect(loan.principalOutstanding.toNumber()).to.equal(LOAN_AMOUNT);
+    expect(loan.collateralAmount.toNumber()).to.equal(COLLATERAL_AMOUNT);
+    expect(await balance(borrower.publicKey, loanMint)).to.equal(
+      borrowerLoanBefore + BigInt(LOAN_AMOUNT - borrowFee)
+    );
+    expect(await balance(teamWallet.publicKey, loanMint)).to.equal(BigInt(borrowFee));
+    expect(await balance(borrower.publicKey, collateralMint)).to.equal(BigInt(10 - COLLATERAL_AMOUNT));
+    expect(await balance(offer.loan, collateralMint)).to.equal(BigInt(COLLATERAL_AMOUNT));
+
+    //  repay, principal and interest go back and the collateral is released
+    const borrowerLoanAfterBorrow = await balance(borrower.publicKey, loanMint);
+    await (await repayMethod(offer, borrower)).rpc();
+
+    expect(await program.account.loan.fetchNullable(offer.loan)).to.equal(null);
+    expect(await balance(borrower.publicKey, collateralMint)).to.equal(BigInt(10));
+
+    const lenderReceived = await balance(lender.publicKey, loanMint);
+    const teamInterestFee = (await balance(teamWallet.publicKey, loanMint)) - BigInt(borrowFee);
+    const interest = lenderReceived - BigInt(LOAN_AMOUNT) + teamInterestFee;
+    expect(lenderReceived >= BigInt(LOAN_AMOUNT)).to.equal(true);
+    //  repaid within seconds of borrowing, interest is a handful of units at most
+    expect(interest >= BigInt(0) && interest <= BigInt(10)).to.equal(true);
+    expect(teamInterestFee).to.equal((interest * BigInt(LEND_FEE) + BigInt(9_999)) / BigInt(10_000));
+    expect(await balance(borrower.publicKey, loanMint)).to.equal(
+      borrowerLoanAfterBorrow - BigInt(LOAN_AMOUNT) - interest
+    );
+
+    //  both portfolios are empty again but keep the lifetime totals
+    const lenderProfile = await program.account.userProfile.fetch(profilePda(lender.publicKey));
+    const borrowerProfile = await program.account.userProfile.fetch(profilePda(borrower.publicKey));
+    expect(lenderProfile.asLender.activeCount).to.equal(0);
+    expect(lenderProfile.asLender.lifetimeCount.toNumber()).to.equal(1);
+    expect(borrowerProfile.asBorrower.activeCount).to.equal(0);
+    expect(borrowerProfile.asBorrower.lifetimeVolume.toNumber()).to.equal(LOAN_AMOUNT);
+  });
+});
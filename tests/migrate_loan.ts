@@ -0,0 +1,64 @@
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { Keypair, LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { ata, balance, cancelMethod, connection, fundedWallet, loanPda, pda, program } from "./setup";
+
+//  the accounts in tests/fixtures/legacy_loan, loaded into the validator by Anchor.toml
+//  an offer of 1 token of a 6 decimal mint that nobody borrowed, written by the original program
+const lender = Keypair.fromSeed(Buffer.alloc(32, 0x4c));
+const loanKey = new PublicKey("Bswb3UyeD1pUTaGiE6WvqwFpJZsQSEY1xhJePCDTHdvp");
+const loanMint = new PublicKey("BwrtBnSeoK7hbfXDfPqr8p2aYj5c7JDqX6yJSaG42yFX");
+const collateralMint = new PublicKey("C1nBL5ufPcQvjkmj6hAmRgoLntHonMuf5WdxVxJenJaE");
+const LOAN_AMOUNT = 1_000_000;
+
+describe("migrate_loan", () => {
+  const legacyLoan = pda(Buffer.from("loan"), loanKey.toBuffer());
+  const loan = loanPda(lender.publicKey, loanKey);
+
+  before(async () => {
+    const signature = await connection.requestAirdrop(lender.publicKey, LAMPORTS_PER_SOL);
+    await connection.confirmTransaction(signature, "confirmed");
+  });
+
+  it("moves a legacy loan to its canonical address and cancels it there", async () => {
+    expect(await balance(legacyLoan, loanMint)).to.equal(BigInt(LOAN_AMOUNT));
+
+    const payer = await fundedWallet();
+    await program.methods
+      .migrateLoan()
+      .accountsPartial({
+        legacyLoan,
+        loan,
+        loanMint,
+        collateralMint,
+        legacyLoanMintAta: ata(legacyLoan, loanMint),
+        legacyCollateralAta: ata(legacyLoan, collateralMint),
+        loanMintAta: ata(loan, loanMint),
+        loanCollateralAta: ata(loan, collateralMint),
+        lender: lender.publicKey,
+        loanKey,
+        payer: payer.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([payer])
+      .rpc();
+
+    //  the old loan and its vaults are gone, the funds sit in the new loan's vault
+    expect(await connection.getAccountInfo(legacyLoan)).to.equal(null);
+    expect(await connection.getAccountInfo(ata(legacyLoan, loanMint))).to.equal(null);
+    expect(await connection.getAccountInfo(ata(legacyLoan, collateralMint))).to.equal(null);
+    expect(await balance(loan, loanMint)).to.equal(BigInt(LOAN_AMOUNT));
+
+    const migrated = await program.account.loan.fetch(loan);
+    expect(migrated.status).to.deep.equal({ offered: {} });
+    expect(migrated.lender.toBase58()).to.equal(lender.publicKey.toBase58());
+    expect(migrated.loanAmount.toNumber()).to.equal(LOAN_AMOUNT);
+    expect(migrated.collateralAmount.toNumber()).to.equal(5);
+
+    //  the lender can act on the migrated loan, cancelling refunds the principal
+    await (await cancelMethod({ lender, loanKey, loan, loanMint, collateralMint })).rpc();
+
+    expect(await program.account.loan.fetchNullable(loan)).to.equal(null);
+    expect(await balance(lender.publicKey, loanMint)).to.equal(BigInt(LOAN_AMOUNT));
+  });
+});
@@ -0,0 +1,103 @@
+import { BN } from "@coral-xyz/anchor";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  allowAsset,
+  assetPda,
+  ata,
+  balance,
+  borrowerPagePda,
+  configPda,
+  ensureConfig,
+  fundedWallet,
+  loanPda,
+  mintTokens,
+  newMint,
+  nextPageIndex,
+  pageOfSlot,
+  profilePda,
+  program,
+} from "./setup";
+
+const COLLATERAL_AMOUNT = 5;
+
+describe("request portfolio", () => {
+  let borrower: Keypair;
+  let loanMint: PublicKey;
+  let collateralMint: PublicKey;
+
+  before(async () => {
+    await ensureConfig();
+
+    loanMint = await newMint(6);
+    collateralMint = await newMint(0);
+    await allowAsset(loanMint);
+    await allowAsset(collateralMint);
+
+    borrower = await fundedWallet();
+    await mintTokens(collateralMint, borrower.publicKey, COLLATERAL_AMOUNT);
+  });
+
+  it("lists a request for the borrower until it is cancelled", async () => {
+    const loanKey = await newMint(0);
+    //  a request lives at the borrower's loan address until a lender fills it
+    const request = loanPda(borrower.publicKey, loanKey);
+    const profile = await program.account.userProfile.fetchNullable(profilePda(borrower.publicKey));
+    const pageIndex = profile ? nextPageIndex(profile.asBorrower.nextSlot) : 0;
+
+    await program.methods
+      .createRequest(new BN(10_000_000), new BN(1_000), new BN(30), new BN(COLLATERAL_AMOUNT))
+      .accountsPartial({
+        globalConfig: configPda,
+        loanAsset: assetPda(loanMint),
+        collateralAsset: assetPda(collateralMint),
+        request,
+        collateralMint,
+        loanMint,
+        requestCollateralAta: ata(request, collateralMint),
+        borrowerCollateralAta: ata(borrower.publicKey, collateralMint),
+        borrower: borrower.publicKey,
+        loanKey,
+        borrowerProfile: profilePda(borrower.publicKey),
+        borrowerPage: borrowerPagePda(borrower.publicKey, pageIndex),
+        loanTokenProgram: TOKEN_PROGRAM_ID,
+        collateralTokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([borrower])
+      .rpc();
+
+    const requested = await program.account.loan.fetch(request);
+    expect(requested.borrowerSlot.isZero()).to.equal(false);
+    const page = borrowerPagePda(borrower.publicKey, pageOfSlot(requested.borrowerSlot));
+    const listed = await program.account.loanPage.fetch(page);
+    expect(listed.loans.map((key) => key.toBase58())).to.include(request.toBase58());
+    let borrowerProfile = await program.account.userProfile.fetch(profilePda(borrower.publicKey));
+    expect(borrowerProfile.asBorrower.activeCount).to.equal(1);
+    //  only a filled request counts towards the lifetime totals
+    expect(borrowerProfile.asBorrower.lifetimeCount.toNumber()).to.equal(0);
+
+    await program.methods
+      .cancelRequest()
+      .accountsPartial({
+        request,
+        collateralMint,
+        requestCollateralAta: ata(request, collateralMint),
+        borrowerCollateralAta: ata(borrower.publicKey, collateralMint),
+        borrower: borrower.publicKey,
+        loanKey,
+        borrowerProfile: profilePda(borrower.publicKey),
+        borrowerPage: page,
+        collateralTokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([borrower])
+      .rpc();
+
+    expect(await program.account.loan.fetchNullable(request)).to.equal(null);
+    expect(await balance(borrower.publicKey, collateralMint)).to.equal(BigInt(COLLATERAL_AMOUNT));
+    borrowerProfile = await program.account.userProfile.fetch(profilePda(borrower.publicKey));
+    expect(borrowerProfile.asBorrower.activeCount).to.equal(0);
+    const unlisted = await program.account.loanPage.fetch(page);
+    expect(unlisted.loans.map((key) => key.toBase58())).to.not.include(request.toBase58());
+  });
+});
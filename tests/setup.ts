@@ -0,0 +1,299 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import {
+  createMint,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+  TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
+import { Keypair, LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { Lending } from "../target/types/lending";
+
+export const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+export const program = anchor.workspace.Lending as Program<Lending>;
+export const connection = provider.connection;
+export const admin = (provider.wallet as anchor.Wallet).payer;
+
+export const NO_FEED = Array(32).fill(0);
+export const UNPRICED = {
+  minCollateralRatioBps: new BN(0),
+  loanPriceFeed: NO_FEED,
+  collateralPriceFeed: NO_FEED,
+  liquidationThresholdBps: new BN(0),
+};
+
+export function pda(...seeds: (Buffer | Uint8Array)[]): PublicKey {
+  return PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+}
+
+export function u64Seed(value: number): Buffer {
+  return new BN(value).toArrayLike(Buffer, "le", 8);
+}
+
+export const configPda = pda(Buffer.from("config"));
+
+//  anchor test deploys the program with the provider wallet as its upgrade authority
+export const BPF_LOADER_UPGRADEABLE_ID = new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111");
+export const programData = PublicKey.findProgramAddressSync(
+  [program.programId.toBuffer()],
+  BPF_LOADER_UPGRADEABLE_ID
+)[0];
+
+export function assetPda(mint: PublicKey): PublicKey {
+  return pda(Buffer.from("asset"), mint.toBuffer());
+}
+
+export function profilePda(wallet: PublicKey): PublicKey {
+  return pda(Buffer.from("profile"), wallet.toBuffer());
+}
+
+export function lenderPagePda(wallet: PublicKey, index = 0): PublicKey {
+  return pda(Buffer.from("lender_page"), wallet.toBuffer(), u64Seed(index));
+}
+
+export function borrowerPagePda(wallet: PublicKey, index = 0): PublicKey {
+  return pda(Buffer.from("borrower_page"), wallet.toBuffer(), u64Seed(index));
+}
+
+export function loanPda(lender: PublicKey, loanKey: PublicKey): PublicKey {
+  return pda(Buffer.from("loan"), lender.toBuffer(), loanKey.toBuffer());
+}
+
+export function ata(owner: PublicKey, mint: PublicKey): PublicKey {
+  return getAssociatedTokenAddressSync(mint, owner, true, TOKEN_PROGRAM_ID);
+}
+
+export async function balance(owner: PublicKey, mint: PublicKey): Promise<bigint> {
+  try {
+    return (await getAccount(connection, ata(owner, mint))).amount;
+  } catch {
+    return BigInt(0);
+  }
+}
+
+//  awaits a transaction that must fail with the program error `code`
+export async function expectError(transaction: Promise<unknown>, code: string) {
+  let error: any;
+  try {
+    await transaction;
+  } catch (err) {
+    error = err;
+  }
+  expect(error, `expected ${code}`).to.not.equal(undefined);
+  expect(error.error?.errorCode?.code, String(error)).to.equal(code);
+}
+
+export async function fundedWallet(sol = 10): Promise<Keypair> {
+  const wallet = Keypair.generate();
+  const signature = await connection.requestAirdrop(wallet.publicKey, sol * LAMPORTS_PER_SOL);
+  await connection.confirmTransaction(signature, "confirmed");
+  return wallet;
+}
+
+export async function newMint(decimals: number): Promise<PublicKey> {
+  return createMint(connection, admin, admin.publicKey, null, decimals);
+}
+
+export async function mintTokens(mint: PublicKey, owner: PublicKey, amount: number | bigint) {
+  const account = await getOrCreateAssociatedTokenAccount(connection, admin, mint, owner, true);
+  await mintTo(connection, admin, mint, account.address, admin, amount);
+}
+
+//  the config is a singleton, the first suite to run creates it and the rest reuse it
+export const teamWallet = Keypair.generate();
+export const LEND_FEE = 1_000;
+export const BORROW_FEE = 100;
+export const MAX_FEE = 2_000;
+
+export async function ensureConfig() {
+  if (await connection.getAccountInfo(configPda)) {
+    return;
+  }
+  await program.methods
+    .initializeConfig(teamWallet.publicKey, new BN(LEND_FEE), new BN(BORROW_FEE), new BN(0), new BN(MAX_FEE), 7)
+    .accountsPartial({ payer: admin.publicKey, programData })
+    .rpc();
+}
+
+export async function allowAsset(mint: PublicKey, borrowFee: BN | null = null) {
+  await program.methods
+    .setAssetConfig({
+      allowedAsLoan: true,
+      allowedAsCollateral: true,
+      minLoanAmount: new BN(0),
+      maxLoanAmount: new BN(0),
+      borrowFee,
+      priceFeed: NO_FEED,
+    })
+    .accountsPartial({ authority: admin.publicKey, mint })
+    .rpc();
+}
+
+export async function setMaxFee(maxFee: number) {
+  await program.methods.setMaxFee(new BN(maxFee)).accountsPartial({ authority: admin.publicKey }).rpc();
+}
+
+export interface Offer {
+  lender: Keypair;
+  loanKey: PublicKey;
+  loan: PublicKey;
+  loanMint: PublicKey;
+  collateralMint: PublicKey;
+}
+
+export async function createOffer(
+  lender: Keypair,
+  loanMint: PublicKey,
+  collateralMint: PublicKey,
+  loanAmount: number,
+  collateralAmount: number,
+  interestRate = 1_000,
+  durationDays = 30
+): Promise<Offer> {
+  const loanKey = await newMint(0);
+  const loan = loanPda(lender.publicKey, loanKey);
+  const profile = await program.account.userProfile.fetchNullable(profilePda(lender.publicKey));
+  const pageIndex = profile ? nextPageIndex(profile.asLender.nextSlot) : 0;
+
+  await program.methods
+    .createLoan(
+      new BN(loanAmount),
+      new BN(interestRate),
+      new BN(durationDays),
+      new BN(collateralAmount),
+      new BN(0),
+      UNPRICED,
+      false
+    )
+    .accountsPartial({
+      globalConfig: configPda,
+      loanAsset: assetPda(loanMint),
+      collateralAsset: assetPda(collateralMint),
+      teamWallet: teamWallet.publicKey,
+      loan,
+      collateralMint,
+      loanMint,
+      loanCollateralAta: ata(loan, collateralMint),
+      loanMintAta: ata(loan, loanMint),
+      lenderLoanAta: ata(lender.publicKey, loanMint),
+      lender: lender.publicKey,
+      loanKey,
+      lenderProfile: profilePda(lender.publicKey),
+      lenderPage: lenderPagePda(lender.publicKey, pageIndex),
+      loanTokenProgram: TOKEN_PROGRAM_ID,
+      collateralTokenProgram: TOKEN_PROGRAM_ID,
+    })
+    .signers([lender])
+    .rpc();
+
+  return { lender, loanKey, loan, loanMint, collateralMint };
+}
+
+//  mirrors PortfolioSide::next_page_index, the page the next listed loan lands in
+export function nextPageIndex(nextSlot: BN): number {
+  return Math.floor(nextSlot.toNumber() / 32);
+}
+
+export async function borrowMethod(
+  offer: Offer,
+  borrower: Keypair,
+  collateralAmount: number,
+  collateralMint = offer.collateralMint
+) {
+  const loan = await program.account.loan.fetch(offer.loan);
+  const profile = await program.account.userProfile.fetchNullable(profilePda(borrower.publicKey));
+  const pageIndex = profile ? nextPageIndex(profile.asBorrower.nextSlot) : 0;
+
+  return program.methods
+    .borrowLoan(new BN(collateralAmount), loan.offerVersion)
+    .accountsPartial({
+      globalConfig: configPda,
+      loan: offer.loan,
+      loanMint: offer.loanMint,
+      loanAsset: assetPda(offer.loanMint),
+      collateralAsset: assetPda(offer.collateralMint),
+      loanCollateralAta: ata(offer.loan, collateralMint),
+      loanMintAta: ata(offer.loan, offer.loanMint),
+      borrowerLoanAta: ata(borrower.publicKey, offer.loanMint),
+      borrowerCollateralAta: ata(borrower.publicKey, collateralMint),
+      teamLoanAta: ata(teamWallet.publicKey, offer.loanMint),
+      teamWallet: teamWallet.publicKey,
+      lender: offer.lender.publicKey,
+      collateralMint,
+      collateralMetadata: null,
+      loanKey: offer.loanKey,
+      borrower: borrower.publicKey,
+      loanPriceUpdate: null,
+      collateralPriceUpdate: null,
+      loanTokenProgram: TOKEN_PROGRAM_ID,
+      collateralTokenProgram: TOKEN_PROGRAM_ID,
+      borrowerProfile: profilePda(borrower.publicKey),
+      borrowerPage: borrowerPagePda(borrower.publicKey, pageIndex),
+      lenderProfile: profilePda(offer.lender.publicKey),
+    })
+    .signers([borrower]);
+}
+
+export async function repayMethod(offer: Offer, borrower: Keypair) {
+  const loan = await program.account.loan.fetch(offer.loan);
+
+  return program.methods
+    .repayLoan()
+    .accountsPartial({
+      globalConfig: configPda,
+      loan: offer.loan,
+      collateralMint: offer.collateralMint,
+      loanMint: offer.loanMint,
+      loanCollateralAta: ata(offer.loan, offer.collateralMint),
+      loanMintAta: ata(offer.loan, offer.loanMint),
+      borrowerCollateralAta: ata(borrower.publicKey, offer.collateralMint),
+      lenderLoanAta: ata(offer.lender.publicKey, offer.loanMint),
+      borrowerLoanAta: ata(borrower.publicKey, offer.loanMint),
+      teamLoanAta: ata(teamWallet.publicKey, offer.loanMint),
+      teamWallet: teamWallet.publicKey,
+      borrower: borrower.publicKey,
+      loanKey: offer.loanKey,
+      lender: offer.lender.publicKey,
+      lenderProfile: profilePda(offer.lender.publicKey),
+      lenderPage: lenderPagePda(offer.lender.publicKey, pageOfSlot(loan.lenderSlot)),
+      borrowerProfile: profilePda(borrower.publicKey),
+      borrowerPage: borrowerPagePda(borrower.publicKey, pageOfSlot(loan.borrowerSlot)),
+      loanTokenProgram: TOKEN_PROGRAM_ID,
+      collateralTokenProgram: TOKEN_PROGRAM_ID,
+    })
+    .signers([borrower]);
+}
+
+export async function cancelMethod(offer: Offer) {
+  const loan = await program.account.loan.fetch(offer.loan);
+  const listed = !loan.lenderSlot.isZero();
+
+  return program.methods
+    .cancelLoan()
+    .accountsPartial({
+      loan: offer.loan,
+      loanMint: offer.loanMint,
+      collateralMint: offer.collateralMint,
+      loanMintAta: ata(offer.loan, offer.loanMint),
+      loanCollateralAta: ata(offer.loan, offer.collateralMint),
+      lenderLoanAta: ata(offer.lender.publicKey, offer.loanMint),
+      lenderCollateralAta: ata(offer.lender.publicKey, offer.collateralMint),
+      lender: offer.lender.publicKey,
+      loanTokenProgram: TOKEN_PROGRAM_ID,
+      collateralTokenProgram: TOKEN_PROGRAM_ID,
+      loanKey: offer.loanKey,
+      lenderProfile: listed ? profilePda(offer.lender.publicKey) : null,
+      lenderPage: listed ? lenderPagePda(offer.lender.publicKey, pageOfSlot(loan.lenderSlot)) : null,
+    })
+    .signers([offer.lender]);
+}
+
+//  mirrors portfolio::page_of_slot
+export function pageOfSlot(slot: BN): number {
+  return slot.isZero() ? 0 : Math.floor((slot.toNumber() - 1) / 32);
+}